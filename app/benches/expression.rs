@@ -0,0 +1,78 @@
+//times evaluating formulas at a view's worth of samples by walking the tree one sample at a time,
+//with the compiled program one sample at a time, and with the compiled program all at once
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rust_grapher::expression::{Definitions, Expression};
+
+const SAMPLES: usize = 4096;
+//how long each way is timed for
+const BUDGET: Duration = Duration::from_millis(500);
+
+//the average time per sample, in nanoseconds
+fn time(mut evaluate: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < BUDGET {
+        evaluate();
+        runs += 1;
+    }
+    start.elapsed().as_nanos() as f64 / (runs * SAMPLES) as f64
+}
+
+fn bench(name: &str, expression: &Expression) {
+    let values = vec![1.5f64; expression.parameters().len()];
+    let mut program = expression.compile();
+    program.bind(&values);
+
+    let xs: Vec<f64> = (0..SAMPLES)
+        .map(|i| -10f64 + 20f64 * i as f64 / SAMPLES as f64)
+        .collect();
+    let mut ys = vec![0f64; SAMPLES];
+
+    let tree = time(|| {
+        for (x, y) in xs.iter().zip(ys.iter_mut()) {
+            *y = expression.eval(black_box(*x), &values);
+        }
+        black_box(&ys);
+    });
+    let single = time(|| {
+        for (x, y) in xs.iter().zip(ys.iter_mut()) {
+            *y = program.eval(black_box(*x));
+        }
+        black_box(&ys);
+    });
+    let many = time(|| {
+        program.eval_many(black_box(&xs), &mut ys);
+        black_box(&ys);
+    });
+
+    println!(
+        "{:<40} tree {:>7.2}ns  compiled {:>7.2}ns  eval_many {:>7.2}ns  {:>5.1}x faster",
+        name,
+        tree,
+        single,
+        many,
+        tree / many
+    );
+}
+
+fn main() {
+    for source in [
+        "2x^2 - 3x + 1",
+        "a*sin(b*x + c)",
+        "exp(-x^2 / 2) / sqrt(2pi)",
+        "max(sin(x), cos(x)) * (1 + 2 + 3)",
+    ] {
+        bench(source, &Expression::parse(source).unwrap());
+    }
+
+    //definitions are inlined when linking, so nesting them costs nothing extra to evaluate
+    let mut definitions = Definitions::default();
+    definitions.define("g(x) = x^2 + 1").unwrap();
+    definitions.define("h(x) = a*sin(g(x)) + g(x)").unwrap();
+    let linked = definitions
+        .link(&Expression::parse("h(x) / g(x)").unwrap(), &mut Vec::new())
+        .unwrap();
+    bench("h(x) / g(x), linked", &linked);
+}
@@ -1,17 +1,47 @@
+use std::path::{Path, PathBuf};
+
 use bevy_ecs::prelude::*;
 
+use rendering::RenderContext;
+use two_dimensional::CameraController;
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    window::{Window, WindowBuilder},
+};
+
+use crate::document::GraphDocument;
+use crate::export::{export_animation, ExportSettings};
+use crate::expression::{Definitions, Expression};
+use crate::graph::{
+    EquationBox, FitToContent, Formula, FrameTime, KeyMap, PlotBundle, Redraw, Theme, WindowEvents,
 };
+use crate::schedule::{frame_schedule, startup_schedule, AppExit};
+
+//where the scene is saved when no document was opened
+const DEFAULT_DOCUMENT_PATH: &str = "graph.ron";
+//where ctrl+e exports the animation to
+const DEFAULT_EXPORT_PATH: &str = "animation.png";
 
-use crate::graph::GraphRenderContext;
+//widthxheight, like 800x600, there is nothing to capture if either is zero
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.split_once('x')?;
+    let size = (width.parse().ok()?, height.parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+fn export(world: &mut World, path: &Path, settings: &ExportSettings) {
+    match export_animation(world, path, settings) {
+        Ok(frames) => log::info!("exported {} frames to {}", frames, path.display()),
+        Err(e) => log::error!("{}: {}", path.display(), e),
+    }
+}
 
 pub struct App;
 
 impl App {
-    //for now we're doing event based updates, when there are no more events we draw to the screen
+    //frames are drawn when events come in, and back to back while anything is animating,
+    //otherwise the loop sleeps until the next event
     pub async fn run() {
 
         env_logger::init();
@@ -20,56 +50,238 @@ impl App {
             .with_title("Grapher")
             .build(&event_loop)
             .unwrap();
+        let window_id = window.id();
+
+        //an optional document to open, it is also where we save to,
+        //--theme with the name of a built in theme or a theme file, which wins over the document's theme,
+        //--keymap with a file of keyboard bindings, any number of --define with a function
+        //like g(x) = x^2 + 1 and any number of --plot with a formula to add,
+        //a definition given to --plot is defined and plotted
+        //--export with a path writes the animation there and quits instead of opening the window,
+        //--export-size, --export-fps and --export-seconds change what it writes, and ctrl+e too
+        let mut document_path = None;
+        let mut theme_name = None;
+        let mut key_map_path = None;
+        let mut definitions = Vec::new();
+        let mut formulas = Vec::new();
+        let mut export_path = None;
+        let mut export_settings = ExportSettings::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--theme" => theme_name = args.next(),
+                "--keymap" => key_map_path = args.next(),
+                "--define" => definitions.extend(args.next()),
+                "--plot" => formulas.extend(args.next()),
+                "--export" => export_path = args.next().map(PathBuf::from),
+                "--export-size" => match args.next().as_deref().and_then(parse_size) {
+                    Some((width, height)) => {
+                        export_settings.width = width;
+                        export_settings.height = height;
+                    }
+                    None => log::error!("--export-size takes a nonzero size like 800x600"),
+                },
+                "--export-fps" => match args.next().and_then(|fps| fps.parse().ok()) {
+                    Some(fps) if fps > 0 => export_settings.fps = fps,
+                    _ => log::error!("--export-fps takes a whole number of frames per second, up to 65535"),
+                },
+                "--export-seconds" => match args.next().and_then(|seconds| seconds.parse::<f32>().ok()) {
+                    Some(seconds) if seconds.is_finite() && seconds > 0f32 => {
+                        export_settings.seconds = Some(seconds)
+                    }
+                    _ => log::error!("--export-seconds takes a number of seconds greater than zero"),
+                },
+                //every option takes a value, so a mistyped one skips its value too rather than
+                //opening the value as the document and saving over it
+                _ if arg.starts_with("--") => match args.next() {
+                    Some(value) => log::error!("unknown option {}, skipping it and {}", arg, value),
+                    None => log::error!("unknown option {}, skipping it", arg),
+                },
+                _ if document_path.is_some() => {
+                    log::error!("only one document can be opened, skipping {}", arg)
+                }
+                _ => document_path = Some(PathBuf::from(arg)),
+            }
+        }
+        let document_path = document_path.unwrap_or_else(|| PathBuf::from(DEFAULT_DOCUMENT_PATH));
+
+        //create our world here, the window and render context are the only things the schedules don't create
+        let render_context = RenderContext::new(&window).await;
+        let mut world = World::new();
+        world.insert_resource(window);
+        world.insert_resource(render_context);
+        world.insert_resource(WindowEvents::default());
+
+        startup_schedule().run(&mut world);
+        if document_path.exists() {
+            match GraphDocument::load(&document_path) {
+                Ok(document) => {
+                    let fit = document.camera.is_none();
+                    document.apply(&mut world);
+                    if fit {
+                        world.resource_mut::<Events<FitToContent>>().send(FitToContent);
+                    }
+                }
+                Err(e) => log::error!("{}: {}", document_path.display(), e),
+            }
+        }
+        for source in &definitions {
+            if let Err(e) = world.resource_mut::<Definitions>().define(source) {
+                log::error!("{}: {}", source, e);
+            }
+        }
+        for source in formulas {
+            //plotting a definition plots it by name, so it follows any later change to it
+            let formula = if source.contains('=') {
+                world
+                    .resource_mut::<Definitions>()
+                    .define(&source)
+                    .map_err(|e| e.to_string())
+                    .and_then(|name| Formula::named(&name).map_err(|e| e.to_string()))
+            } else {
+                Expression::parse(&source)
+                    .map(Formula::new)
+                    .map_err(|e| e.to_string())
+            };
+            match formula {
+                Ok(formula) => {
+                    world
+                        .spawn()
+                        .insert_bundle(PlotBundle::new(EquationBox::new(formula)));
+                }
+                Err(e) => log::error!("{}: {}", source, e),
+            }
+        }
+        if let Some(theme_name) = theme_name {
+            match Theme::from_name_or_path(&theme_name) {
+                Ok(theme) => world.insert_resource(theme),
+                Err(e) => log::error!("{}: {}", theme_name, e),
+            }
+        }
+        if let Some(key_map_path) = key_map_path {
+            match KeyMap::load(&key_map_path) {
+                Ok(key_map) => world.insert_resource(key_map),
+                Err(e) => log::error!("{}: {}", key_map_path, e),
+            }
+        }
+
+        if let Some(export_path) = export_path {
+            export(&mut world, &export_path, &export_settings);
+            return;
+        }
 
-        //create our world here, and add renderables to it
-        let world = World::new();
-        
-        //create our renderer and our graph here
-        let mut graph_render_context = GraphRenderContext::new(&window).await;
+        let mut schedule = frame_schedule();
+        let mut modifiers = ModifiersState::empty();
+        //the first frame is always drawn
+        let mut redraw = true;
 
-        //store a flag for if our view changed and then update all the components before rendering
         event_loop.run(move |event, _, control_flow| match event {
             Event::WindowEvent {
-                ref event,
-                window_id,
-            } if window_id == window.id() => match event {
-                WindowEvent::CloseRequested
-                | WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: Some(VirtualKeyCode::Escape),
-                            ..
-                        },
-                    ..
-                } => *control_flow = ControlFlow::Exit,
-                //handle resizes
-                WindowEvent::Resized(new_size) => {
-                    renderer.resize(Some(*new_size));
+                event,
+                window_id: id,
+            } if id == window_id => {
+                redraw = true;
+                match event {
+                    WindowEvent::CloseRequested
+                    | WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => *control_flow = ControlFlow::Exit,
+                    //the input systems track the modifiers too
+                    WindowEvent::ModifiersChanged(new_modifiers) => {
+                        modifiers = new_modifiers;
+                        world
+                            .resource_mut::<WindowEvents>()
+                            .send(WindowEvent::ModifiersChanged(new_modifiers));
+                    }
+                    //ctrl+s saves the scene back to the document
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::S),
+                                ..
+                            },
+                        ..
+                    } if modifiers.ctrl() => {
+                        match GraphDocument::from_world(&mut world).save(&document_path) {
+                            Ok(()) => log::info!("saved {}", document_path.display()),
+                            Err(e) => log::error!("{}: {}", document_path.display(), e),
+                        }
+                    }
+                    //ctrl+e exports the animation, the window stops updating until it is done
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::E),
+                                ..
+                            },
+                        ..
+                    } if modifiers.ctrl() => {
+                        export(&mut world, Path::new(DEFAULT_EXPORT_PATH), &export_settings);
+                    }
+                    //ctrl+t cycles through the built in themes
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::T),
+                                ..
+                            },
+                        ..
+                    } if modifiers.ctrl() => {
+                        let next = world.resource::<Theme>().next_builtin();
+                        world.insert_resource(next);
+                    }
+                    //the new size is borrowed, so it can't be stored as is
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        new_inner_size,
+                    } => {
+                        world
+                            .resource_mut::<CameraController>()
+                            .set_scale_factor(scale_factor);
+                        world
+                            .resource_mut::<WindowEvents>()
+                            .send(WindowEvent::Resized(*new_inner_size));
+                    }
+                    //everything else is handled by the systems in the input stage
+                    event => {
+                        if let Some(event) = event.to_static() {
+                            world.resource_mut::<WindowEvents>().send(event);
+                        }
+                    }
                 }
-                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                    renderer.resize(Some(**new_inner_size));
-                },
-                e => {
-                    renderer.event(e);
+            }
+            Event::RedrawRequested(id) if id == window_id => {
+                schedule.run(&mut world);
+                //change detection compares against the previous frame, so forget this frame's removals
+                world.clear_trackers();
+
+                //keep drawing while something animates, otherwise sleep until the next event
+                redraw = world.resource_mut::<Redraw>().take();
+                if !redraw {
+                    world.resource_mut::<FrameTime>().pause();
                 }
-            },
-            Event::RedrawRequested(window_id) if window_id == window.id() => {
-                renderer.update();
-                match renderer.render() {
-                    Ok(_) => {}
-                    // Reconfigure the surface if lost
-                    Err(wgpu::SurfaceError::Lost) => renderer.resize(None),
-                    // The system is out of memory, we should probably quit
-                    Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
-                    // All other errors (Outdated, Timeout) should be resolved by the next frame
-                    Err(e) => eprintln!("{:?}", e),
+                *control_flow = if redraw {
+                    ControlFlow::Poll
+                } else {
+                    ControlFlow::Wait
+                };
+                if world.contains_resource::<AppExit>() {
+                    *control_flow = ControlFlow::Exit;
                 }
             }
-            Event::MainEventsCleared => {
-                // RedrawRequested will only trigger once, unless we manually
-                // request it.
-                window.request_redraw();
+            // RedrawRequested will only trigger once, unless we manually
+            // request it.
+            Event::MainEventsCleared if redraw => {
+                world.resource::<Window>().request_redraw();
             }
             _ => {}
         });
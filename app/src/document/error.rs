@@ -0,0 +1,47 @@
+use std::fmt;
+
+use super::CURRENT_VERSION;
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    //the document was written by a newer (or unknown) version of the format
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't access document: {}", e),
+            Self::Parse(e) => write!(f, "couldn't parse document: {}", e),
+            Self::Serialize(e) => write!(f, "couldn't serialize document: {}", e),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "document version {} is not supported, this build reads up to version {}",
+                version, CURRENT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<std::io::Error> for DocumentError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for DocumentError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl From<ron::Error> for DocumentError {
+    fn from(e: ron::Error) -> Self {
+        Self::Serialize(e)
+    }
+}
@@ -0,0 +1,425 @@
+use std::{fs, path::Path, str::FromStr};
+
+use bevy_ecs::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use two_dimensional::CameraController;
+
+use super::{migrate, DocumentError};
+use crate::expression::{Definitions, Expression, ParseError};
+use crate::graph::{
+    Circle, ControlPointBundle, DrivenBy, EquationBox, Fill, Formula, GridSettings, Normal, Parameters, Plot,
+    PlotBundle, Stroke, TangentLine, Theme, Visibility, ZOrder,
+};
+
+type SavedPlot = (
+    Entity,
+    &'static Plot,
+    Option<&'static DrivenBy>,
+    Option<&'static Stroke>,
+    Option<&'static Fill>,
+    Option<&'static Visibility>,
+    Option<&'static ZOrder>,
+);
+
+//bump this whenever the layout below changes, and teach migrate how to read the old one
+pub const CURRENT_VERSION: u32 = 1;
+
+//everything needed to rebuild a scene: where the camera is looking, how the graph is drawn,
+//and every plotted entity along with its style
+//documents without a camera, usually written by hand, are fit to their content when opened
+#[derive(Serialize, Deserialize)]
+pub struct GraphDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub camera: Option<CameraDocument>,
+    pub theme: Theme,
+    pub grid: GridSettings,
+    //the values and slider ranges of the parameters the formulas read
+    #[serde(default)]
+    pub parameters: Parameters,
+    //the functions the formulas can call, as they were typed, like g(x) = x^2 + 1
+    #[serde(default)]
+    pub definitions: Vec<String>,
+    pub plots: Vec<EquationPlotDocument>,
+    //the points that can be dragged around, shapes refer to the ones they follow by their index here
+    #[serde(default)]
+    pub points: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub shapes: Vec<ShapeDocument>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CameraDocument {
+    pub center: (f32, f32),
+    //pixels per graph unit along x and y
+    pub scale: (f32, f32),
+    pub lock_aspect: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EquationPlotDocument {
+    pub equation: EquationDocument,
+    pub stroke: Stroke,
+    pub fill: Fill,
+    pub visibility: Visibility,
+    pub z_order: ZOrder,
+}
+
+//a plot that isn't an equation, usually one that follows control points
+#[derive(Serialize, Deserialize)]
+pub struct ShapeDocument {
+    pub shape: ShapeKind,
+    //indices into the document's points, in the order the shape takes them, see Plottable::drive
+    #[serde(default)]
+    pub driven_by: Vec<usize>,
+    pub stroke: Stroke,
+    pub fill: Fill,
+    pub visibility: Visibility,
+    pub z_order: ZOrder,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ShapeKind {
+    Circle(Circle),
+    //the line touching equation at x
+    Tangent { equation: EquationDocument, x: f32 },
+}
+
+//what a plot saves of itself, see Plottable::to_document, from_world adds its style
+//and sorts it into the document's plots, points or shapes
+pub enum PlotDocument {
+    Equation(EquationDocument),
+    //a control point
+    Point((f32, f32)),
+    Shape(ShapeKind),
+}
+
+impl ShapeKind {
+    fn into_bundle(self) -> Result<PlotBundle, ParseError> {
+        Ok(match self {
+            Self::Circle(circle) => PlotBundle::new(circle),
+            Self::Tangent { equation, x } => PlotBundle::new(TangentLine::new(equation.try_into()?, x)),
+        })
+    }
+}
+
+//the saved form of every kind of equation the format knows about
+#[derive(Serialize, Deserialize)]
+pub enum EquationDocument {
+    Normal(Normal),
+    //the source of a formula, as it was typed
+    Expression(String),
+}
+
+//formulas are parsed again when they are loaded, which fails if the file was edited by hand
+impl TryFrom<EquationDocument> for EquationBox {
+    type Error = ParseError;
+
+    fn try_from(document: EquationDocument) -> Result<Self, Self::Error> {
+        Ok(match document {
+            EquationDocument::Normal(normal) => EquationBox::new(normal),
+            EquationDocument::Expression(source) => EquationBox::new(Formula::new(Expression::parse(&source)?)),
+        })
+    }
+}
+
+impl GraphDocument {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DocumentError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DocumentError> {
+        let source = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        fs::write(path, source)?;
+        Ok(())
+    }
+
+    //capture the scene currently in the world, plots that can't be saved are skipped
+    pub fn from_world(world: &mut World) -> Self {
+        let camera = world
+            .get_resource::<CameraController>()
+            .map(|camera| CameraDocument {
+                center: camera.center(),
+                scale: camera.scale(),
+                lock_aspect: camera.lock_aspect(),
+            });
+
+        let theme = world.get_resource::<Theme>().cloned().unwrap_or_default();
+
+        let grid = world
+            .get_resource::<GridSettings>()
+            .cloned()
+            .unwrap_or_default();
+
+        let parameters = world
+            .get_resource::<Parameters>()
+            .cloned()
+            .unwrap_or_default();
+
+        let definitions = world
+            .get_resource::<Definitions>()
+            .map(|definitions| definitions.iter().map(|definition| definition.source()).collect())
+            .unwrap_or_default();
+
+        //shapes refer to the points driving them by their index, so they wait until every point is known
+        let mut points = Vec::new();
+        let mut point_entities = Vec::new();
+        let mut plots = Vec::new();
+        let mut driven_shapes = Vec::new();
+        let mut query = world.query::<SavedPlot>();
+        for (entity, plot, driven_by, stroke, fill, visibility, z_order) in query.iter(world) {
+            let stroke = stroke.cloned().unwrap_or_default();
+            let fill = fill.cloned().unwrap_or_default();
+            let visibility = visibility.cloned().unwrap_or_default();
+            let z_order = z_order.copied().unwrap_or_default();
+            match plot.to_document() {
+                Some(PlotDocument::Equation(equation)) => plots.push(EquationPlotDocument {
+                    equation,
+                    stroke,
+                    fill,
+                    visibility,
+                    z_order,
+                }),
+                Some(PlotDocument::Point(position)) => {
+                    points.push(position);
+                    point_entities.push(entity);
+                }
+                Some(PlotDocument::Shape(shape)) => {
+                    let driven_by = driven_by.map_or_else(Vec::new, |driven_by| driven_by.0.clone());
+                    let shape = ShapeDocument {
+                        shape,
+                        driven_by: Vec::new(),
+                        stroke,
+                        fill,
+                        visibility,
+                        z_order,
+                    };
+                    driven_shapes.push((shape, driven_by));
+                }
+                None => log::warn!("skipping a plot that can't be saved to a document"),
+            }
+        }
+
+        //points that were despawned since are left out, the shape stays where it is
+        let shapes: Vec<ShapeDocument> = driven_shapes
+            .into_iter()
+            .map(|(shape, driven_by)| ShapeDocument {
+                driven_by: driven_by
+                    .iter()
+                    .filter_map(|source| point_entities.iter().position(|entity| entity == source))
+                    .collect(),
+                ..shape
+            })
+            .collect();
+
+        Self {
+            version: CURRENT_VERSION,
+            camera,
+            theme,
+            grid,
+            parameters,
+            definitions,
+            plots,
+            points,
+            shapes,
+        }
+    }
+
+    //replace the scene in the world with this document
+    //the camera is only updated if it exists, so apply after startup
+    pub fn apply(self, world: &mut World) {
+        if let (Some(document), Some(mut camera)) =
+            (self.camera, world.get_resource_mut::<CameraController>())
+        {
+            let (center_x, center_y) = document.center;
+            camera.set_lock_aspect(document.lock_aspect);
+            camera.look_at(center_x, center_y, document.scale);
+        }
+
+        //apply_theme passes the theme on to the renderer and the palette next frame
+        world.insert_resource(self.theme);
+        world.insert_resource(self.grid);
+        world.insert_resource(self.parameters);
+
+        //link_formulas links every plot against these when they're spawned
+        let mut definitions = Definitions::default();
+        for source in &self.definitions {
+            if let Err(e) = definitions.define(source) {
+                log::error!("skipping the definition {}: {}", source, e);
+            }
+        }
+        world.insert_resource(definitions);
+
+        //clear out the old plots before spawning the new ones
+        let old_plots: Vec<Entity> = world
+            .query_filtered::<Entity, With<Plot>>()
+            .iter(world)
+            .collect();
+        for entity in old_plots {
+            world.despawn(entity);
+        }
+
+        //saved plots keep their colors instead of taking new ones from the palette
+        //a formula that no longer parses is left out, the rest of the document still opens
+        let plots: Vec<PlotBundle> = self
+            .plots
+            .into_iter()
+            .filter_map(|plot| match EquationBox::try_from(plot.equation) {
+                Ok(equation) => Some(
+                    PlotBundle::new(equation)
+                        .with_stroke(plot.stroke)
+                        .with_fill(plot.fill)
+                        .with_visibility(plot.visibility)
+                        .with_z_order(plot.z_order),
+                ),
+                Err(e) => {
+                    log::error!("skipping a formula that doesn't parse: {}", e);
+                    None
+                }
+            })
+            .collect();
+        world.spawn_batch(plots);
+
+        //control points keep their own style, they are always drawn the same way
+        let points: Vec<Entity> = self
+            .points
+            .into_iter()
+            .map(|position| world.spawn().insert_bundle(ControlPointBundle::new(position)).id())
+            .collect();
+        for shape in self.shapes {
+            let bundle = match shape.shape.into_bundle() {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    log::error!("skipping a shape whose formula doesn't parse: {}", e);
+                    continue;
+                }
+            };
+            //drive_dependents moves the shape onto its points on the first frame
+            let driven_by: Vec<Entity> = shape
+                .driven_by
+                .iter()
+                .filter_map(|&index| points.get(index).copied())
+                .collect();
+            let mut entity = world.spawn();
+            entity.insert_bundle(
+                bundle
+                    .with_stroke(shape.stroke)
+                    .with_fill(shape.fill)
+                    .with_visibility(shape.visibility)
+                    .with_z_order(shape.z_order),
+            );
+            if !driven_by.is_empty() {
+                entity.insert(DrivenBy(driven_by));
+            }
+        }
+    }
+}
+
+impl FromStr for GraphDocument {
+    type Err = DocumentError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        migrate::parse(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ControlPoint, Parameter};
+
+    //one of each kind of plot the document saves, with a style that isn't the default
+    fn scene() -> World {
+        let mut world = World::new();
+        let mut parameters = Parameters::default();
+        parameters.insert(
+            "a",
+            Parameter {
+                value: 2.5,
+                ..Parameter::default()
+            },
+        );
+        world.insert_resource(parameters);
+
+        let formula = Formula::new(Expression::parse("a*x^2").unwrap());
+        world.spawn().insert_bundle(
+            PlotBundle::new(EquationBox::new(formula))
+                .with_stroke(Stroke {
+                    color: [0.1, 0.2, 0.3],
+                    width: 3f32,
+                })
+                .with_fill(Fill {
+                    color: Some([0.4, 0.5, 0.6]),
+                })
+                .with_visibility(Visibility { visible: false })
+                .with_z_order(ZOrder(7)),
+        );
+        let point = world
+            .spawn()
+            .insert_bundle(ControlPointBundle::new((1f32, 2f32)))
+            .id();
+        world
+            .spawn()
+            .insert_bundle(PlotBundle::new(Circle::new((1f32, 2f32), 3f32)))
+            .insert(DrivenBy(vec![point]));
+        world
+    }
+
+    #[test]
+    fn saved_documents_load_back() {
+        let path = std::env::temp_dir().join(format!("grapher_round_trip_{}.ron", std::process::id()));
+        GraphDocument::from_world(&mut scene()).save(&path).unwrap();
+        let document = GraphDocument::load(&path);
+        fs::remove_file(&path).unwrap();
+        let document = document.unwrap();
+
+        assert_eq!(document.version, CURRENT_VERSION);
+        assert_eq!(document.parameters.value("a"), 2.5);
+
+        assert_eq!(document.plots.len(), 1);
+        let plot = &document.plots[0];
+        assert!(matches!(&plot.equation, EquationDocument::Expression(source) if source == "a*x^2"));
+        assert_eq!(plot.stroke.color, [0.1, 0.2, 0.3]);
+        assert_eq!(plot.stroke.width, 3f32);
+        assert_eq!(plot.fill.color, Some([0.4, 0.5, 0.6]));
+        assert!(!plot.visibility.visible);
+        assert_eq!(plot.z_order.0, 7);
+
+        assert_eq!(document.points, vec![(1f32, 2f32)]);
+        assert_eq!(document.shapes.len(), 1);
+        let shape = &document.shapes[0];
+        assert!(matches!(
+            &shape.shape,
+            ShapeKind::Circle(circle) if circle.center == (1f32, 2f32) && circle.radius == 3f32
+        ));
+        assert_eq!(shape.driven_by, vec![0]);
+    }
+
+    #[test]
+    fn applied_documents_save_the_same() {
+        let saved = GraphDocument::from_world(&mut scene());
+        let source = ron::to_string(&saved).unwrap();
+
+        let mut world = World::new();
+        source.parse::<GraphDocument>().unwrap().apply(&mut world);
+        assert_eq!(ron::to_string(&GraphDocument::from_world(&mut world)).unwrap(), source);
+
+        //the circle follows the respawned point
+        let point = world
+            .query::<(Entity, &Plot)>()
+            .iter(&world)
+            .find(|(_, plot)| plot.downcast_ref::<ControlPoint>().is_some())
+            .map(|(entity, _)| entity)
+            .unwrap();
+        let driven_by: Vec<&DrivenBy> = world.query::<&DrivenBy>().iter(&world).collect();
+        assert_eq!(driven_by.len(), 1);
+        assert_eq!(driven_by[0].0, vec![point]);
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let document = "(version: 2, plots: [])".parse::<GraphDocument>();
+        assert!(matches!(document, Err(DocumentError::UnsupportedVersion(2))));
+    }
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+use super::{DocumentError, GraphDocument, CURRENT_VERSION};
+
+//every document leads with its version, so we read just that before deciding how to parse the rest
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u32,
+}
+
+//version 1 is the only layout so far, so there is nothing to migrate yet and documents claiming
+//any other version are refused rather than misread
+//when the layout changes, the old one keeps its own structs and gets an arm here converting it forward
+pub fn parse(source: &str) -> Result<GraphDocument, DocumentError> {
+    let VersionHeader { version } = ron::from_str(source)?;
+    match version {
+        CURRENT_VERSION => Ok(ron::from_str(source)?),
+        _ => Err(DocumentError::UnsupportedVersion(version)),
+    }
+}
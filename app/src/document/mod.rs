@@ -0,0 +1,9 @@
+mod error;
+mod graph_document;
+mod migrate;
+
+pub use error::DocumentError;
+pub use graph_document::{
+    CameraDocument, EquationDocument, EquationPlotDocument, GraphDocument, PlotDocument, ShapeDocument,
+    ShapeKind, CURRENT_VERSION,
+};
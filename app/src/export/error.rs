@@ -0,0 +1,55 @@
+use std::fmt;
+
+use rendering::CaptureError;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(std::io::Error),
+    Capture(CaptureError),
+    Image(image::ImageError),
+    Png(png::EncodingError),
+    //the path's extension isn't one of the formats that can be written
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't write export: {}", e),
+            Self::Capture(e) => write!(f, "couldn't capture frame: {}", e),
+            Self::Image(e) => write!(f, "couldn't encode frame: {}", e),
+            Self::Png(e) => write!(f, "couldn't encode animated png: {}", e),
+            Self::UnsupportedFormat(extension) => write!(
+                f,
+                "can't export .{} files, use .png or .apng for an animated png, .gif, or no extension for a directory of pngs",
+                extension
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<CaptureError> for ExportError {
+    fn from(e: CaptureError) -> Self {
+        Self::Capture(e)
+    }
+}
+
+impl From<image::ImageError> for ExportError {
+    fn from(e: image::ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+impl From<png::EncodingError> for ExportError {
+    fn from(e: png::EncodingError) -> Self {
+        Self::Png(e)
+    }
+}
@@ -0,0 +1,180 @@
+mod error;
+mod writer;
+
+use std::path::Path;
+
+use bevy_ecs::prelude::*;
+use rendering::{Capture, Frame, RenderContext};
+use two_dimensional::CameraController;
+use winit::dpi::PhysicalSize;
+
+pub use error::ExportError;
+pub use writer::FrameWriter;
+
+use crate::graph::{Animations, Parameters};
+use crate::schedule::export_schedule;
+
+//how long an export runs when nothing is animated
+const DEFAULT_SECONDS: f32 = 4f32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    //one animated png that loops forever
+    Apng,
+    Gif,
+    //a directory of numbered pngs, for video tools
+    PngSequence,
+}
+
+impl ExportFormat {
+    //.gif writes a gif, .png or .apng an animated png, and a path without an extension a directory of pngs
+    pub fn from_path(path: &Path) -> Result<Self, ExportError> {
+        let extension = match path.extension() {
+            Some(extension) => extension.to_string_lossy().to_ascii_lowercase(),
+            None => return Ok(Self::PngSequence),
+        };
+        match extension.as_str() {
+            "png" | "apng" => Ok(Self::Apng),
+            "gif" => Ok(Self::Gif),
+            _ => Err(ExportError::UnsupportedFormat(extension)),
+        }
+    }
+}
+
+//the pictures an export writes, independent of the window
+#[derive(Clone)]
+pub struct ExportSettings {
+    pub width: u32,
+    pub height: u32,
+    //apngs store the frame delay as a fraction with a 16 bit denominator
+    pub fps: u16,
+    //None runs until every animation has come back around
+    pub seconds: Option<f32>,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            fps: 30,
+            seconds: None,
+        }
+    }
+}
+
+//the live camera at the export's resolution, showing the same part of the graph
+fn export_camera(camera: &CameraController, (width, height): (u32, u32)) -> CameraController {
+    let rect = camera.view().rect();
+    let (center_x, center_y) = camera.center();
+    let mut scale = (
+        width as f32 / (rect.right - rect.left),
+        height as f32 / (rect.top - rect.bottom),
+    );
+    if camera.lock_aspect() {
+        let uniform = scale.0.min(scale.1);
+        scale = (uniform, uniform);
+    }
+
+    let mut export = camera.clone();
+    //pictures don't depend on the display they were exported from
+    export.set_scale_factor(1f64);
+    export.resize(PhysicalSize::new(width, height));
+    export.look_at(center_x, center_y, scale);
+    export
+}
+
+//render the parameter animations offscreen and write them to path, returns how many frames were written
+//frame n shows the timeline n / fps seconds in, computed from the parameters as they were when the
+//export started rather than stepped from the frame before, so frame n always has the same pixels
+//the scene is left as it was once the export is done
+pub fn export_animation(world: &mut World, path: &Path, settings: &ExportSettings) -> Result<usize, ExportError> {
+    let format = ExportFormat::from_path(path)?;
+    let mut schedule = export_schedule();
+    //one run without a frame lets new plots pick up their geometry, colors and parameters
+    schedule.run(world);
+    world.clear_trackers();
+
+    let parameters = world.resource::<Parameters>().clone();
+    let camera = world.resource::<CameraController>().clone();
+    let mut animations = world.resource::<Animations>().clone();
+    if animations.animations.is_empty() {
+        animations.animate_all(&parameters);
+    }
+
+    let size = (settings.width, settings.height);
+    let seconds = settings
+        .seconds
+        .or_else(|| animations.duration())
+        .unwrap_or(DEFAULT_SECONDS);
+    let frames = ((seconds * settings.fps as f32).round() as usize).max(1);
+
+    world.insert_resource(export_camera(&camera, size));
+    let result = export_frames(
+        world,
+        &mut schedule,
+        (path, format),
+        settings,
+        &animations,
+        &parameters,
+        frames,
+    );
+
+    world.insert_resource(parameters);
+    world.insert_resource(camera);
+    result.map(|()| frames)
+}
+
+fn export_frames(
+    world: &mut World,
+    schedule: &mut Schedule,
+    (path, format): (&Path, ExportFormat),
+    settings: &ExportSettings,
+    animations: &Animations,
+    start: &Parameters,
+    frames: usize,
+) -> Result<(), ExportError> {
+    let capture = Capture::new(world.resource::<RenderContext>(), settings.width, settings.height)?;
+    let mut writer = FrameWriter::new(path, format, capture.size(), settings.fps, frames)?;
+
+    for frame in 0..frames {
+        let elapsed = frame as f32 / settings.fps as f32;
+        world.insert_resource(animations.sample(start, elapsed));
+        world.insert_resource(capture.frame());
+        schedule.run(world);
+        world.clear_trackers();
+
+        let frame = world.remove_resource::<Frame>();
+        let render_context = world.resource::<RenderContext>();
+        if let Some(frame) = frame {
+            frame.present(render_context);
+        }
+        writer.write(capture.read(render_context)?, capture.size())?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(path: &str) -> Result<ExportFormat, ExportError> {
+        ExportFormat::from_path(Path::new(path))
+    }
+
+    #[test]
+    fn formats_follow_the_extension() {
+        assert_eq!(format("out.png").unwrap(), ExportFormat::Apng);
+        assert_eq!(format("out.APNG").unwrap(), ExportFormat::Apng);
+        assert_eq!(format("out.gif").unwrap(), ExportFormat::Gif);
+        assert_eq!(format("frames").unwrap(), ExportFormat::PngSequence);
+    }
+
+    #[test]
+    fn other_extensions_are_refused() {
+        for path in ["out.jpg", "out.mp4", "frames.d/out.webp"] {
+            let refused = matches!(format(path), Err(ExportError::UnsupportedFormat(_)));
+            assert!(refused, "{}", path);
+        }
+    }
+}
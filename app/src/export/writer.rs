@@ -0,0 +1,89 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use super::{ExportError, ExportFormat};
+
+//takes the captured frames one at a time, so a long export never holds more than one in memory
+pub enum FrameWriter {
+    Apng(png::Writer<BufWriter<File>>),
+    Gif {
+        encoder: image::codecs::gif::GifEncoder<BufWriter<File>>,
+        delay: image::Delay,
+    },
+    //a directory of numbered pngs
+    Sequence {
+        directory: PathBuf,
+        next: usize,
+    },
+}
+
+impl FrameWriter {
+    //animations loop forever at the given frame rate
+    pub fn new(
+        path: &Path,
+        format: ExportFormat,
+        (width, height): (u32, u32),
+        fps: u16,
+        frames: usize,
+    ) -> Result<Self, ExportError> {
+        match format {
+            ExportFormat::Apng => {
+                let mut encoder =
+                    png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(frames as u32, 0)?;
+                encoder.set_frame_delay(1, fps)?;
+                Ok(Self::Apng(encoder.write_header()?))
+            }
+            ExportFormat::Gif => {
+                let mut encoder =
+                    image::codecs::gif::GifEncoder::new(BufWriter::new(File::create(path)?));
+                encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+                Ok(Self::Gif {
+                    encoder,
+                    delay: image::Delay::from_numer_denom_ms(1000, fps.into()),
+                })
+            }
+            ExportFormat::PngSequence => {
+                fs::create_dir_all(path)?;
+                Ok(Self::Sequence {
+                    directory: path.to_path_buf(),
+                    next: 0,
+                })
+            }
+        }
+    }
+
+    //one frame of tightly packed rgba rows
+    pub fn write(
+        &mut self,
+        pixels: Vec<u8>,
+        (width, height): (u32, u32),
+    ) -> Result<(), ExportError> {
+        match self {
+            Self::Apng(writer) => writer.write_image_data(&pixels)?,
+            Self::Gif { encoder, delay } => {
+                let image = image::RgbaImage::from_raw(width, height, pixels)
+                    .expect("a captured frame always fills the picture");
+                encoder.encode_frame(image::Frame::from_parts(image, 0, 0, *delay))?;
+            }
+            Self::Sequence { directory, next } => {
+                let path = directory.join(format!("frame_{:05}.png", next));
+                image::save_buffer(path, &pixels, width, height, image::ColorType::Rgba8)?;
+                *next += 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), ExportError> {
+        if let Self::Apng(writer) = self {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//the tree an expression is parsed into
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    //the variable being plotted against, or one of a definition's arguments, by position
+    Argument(usize),
+    //an index into the expression's parameter names
+    Parameter(usize),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    //a call to a user defined function, an index into the expression's function names
+    //these are replaced by the function's body when the expression is linked, see Definitions
+    Apply(usize, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+impl BinaryOp {
+    pub fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            Self::Add => a + b,
+            Self::Subtract => a - b,
+            Self::Multiply => a * b,
+            Self::Divide => a / b,
+            Self::Power => a.powf(b),
+        }
+    }
+}
+
+//the built in functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Sqrt,
+    Exp,
+    //natural log
+    Ln,
+    //base 10
+    Log,
+    Abs,
+    Floor,
+    Ceil,
+    Min,
+    Max,
+}
+
+impl Function {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "asin" => Self::Asin,
+            "acos" => Self::Acos,
+            "atan" => Self::Atan,
+            "sinh" => Self::Sinh,
+            "cosh" => Self::Cosh,
+            "tanh" => Self::Tanh,
+            "sqrt" => Self::Sqrt,
+            "exp" => Self::Exp,
+            "ln" => Self::Ln,
+            "log" => Self::Log,
+            "abs" => Self::Abs,
+            "floor" => Self::Floor,
+            "ceil" => Self::Ceil,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            _ => return None,
+        })
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Self::Min | Self::Max => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn apply(self, args: &[f64]) -> f64 {
+        let a = args[0];
+        match self {
+            Self::Sin => a.sin(),
+            Self::Cos => a.cos(),
+            Self::Tan => a.tan(),
+            Self::Asin => a.asin(),
+            Self::Acos => a.acos(),
+            Self::Atan => a.atan(),
+            Self::Sinh => a.sinh(),
+            Self::Cosh => a.cosh(),
+            Self::Tanh => a.tanh(),
+            Self::Sqrt => a.sqrt(),
+            Self::Exp => a.exp(),
+            Self::Ln => a.ln(),
+            Self::Log => a.log10(),
+            Self::Abs => a.abs(),
+            Self::Floor => a.floor(),
+            Self::Ceil => a.ceil(),
+            Self::Min => a.min(args[1]),
+            Self::Max => a.max(args[1]),
+        }
+    }
+}
+
+impl Expr {
+    //walk the tree, parameters are read from values by index
+    //user defined functions that weren't linked have no value
+    pub fn eval(&self, x: f64, values: &[f64]) -> f64 {
+        match self {
+            Self::Number(n) => *n,
+            Self::Argument(_) => x,
+            Self::Parameter(i) => values[*i],
+            Self::Negate(a) => -a.eval(x, values),
+            Self::Binary(op, a, b) => op.apply(a.eval(x, values), b.eval(x, values)),
+            Self::Call(function, args) => {
+                let args: Vec<f64> = args.iter().map(|arg| arg.eval(x, values)).collect();
+                function.apply(&args)
+            }
+            Self::Apply(..) => f64::NAN,
+        }
+    }
+}
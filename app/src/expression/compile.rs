@@ -0,0 +1,346 @@
+use super::{BinaryOp, Expr, Expression, Function};
+
+//how many samples are evaluated together, every register holds this many values
+const LANES: usize = 64;
+//programs needing no more registers than this evaluate single samples without allocating
+const STACK_REGISTERS: usize = 16;
+
+//each instruction works on whole registers at once, one value per sample
+//operations write their result over their first operand, which keeps every register in use
+//below the ones being written and lets the loops run in place
+#[derive(Debug, Clone, Copy)]
+enum Instruction {
+    Constant { out: usize, value: f64 },
+    Argument { out: usize },
+    Parameter { out: usize, index: usize },
+    Negate { a: usize },
+    Binary { op: BinaryOp, a: usize, b: usize },
+    //powers with a small whole exponent, much cheaper than powf
+    PowI { a: usize, n: i32 },
+    Unary { function: fn(f64) -> f64, a: usize },
+    Min { a: usize, b: usize },
+    Max { a: usize, b: usize },
+}
+
+//an expression flattened into straight line code for a register machine, for evaluating it at many
+//samples without walking the tree for each one
+//parts that don't depend on x or a parameter are worked out once when compiling
+#[derive(Debug, Clone)]
+pub struct Program {
+    code: Vec<Instruction>,
+    registers: usize,
+    //the parameter values, in the order of the expression's parameters
+    values: Vec<f64>,
+}
+
+impl Program {
+    //parameters start at zero until they are bound
+    //calls to user defined functions have to be linked first, see Definitions, or they have no value
+    pub fn compile(expression: &Expression) -> Self {
+        let mut program = Self {
+            code: Vec::new(),
+            registers: 1,
+            values: vec![0f64; expression.parameters().len()],
+        };
+        program.emit(expression.ast(), 0);
+        program
+    }
+
+    //the values of the expression's parameters in order, parameters left without one go back to zero
+    //and values past the last parameter are ignored
+    pub fn bind(&mut self, values: &[f64]) {
+        let bound = values.len().min(self.values.len());
+        self.values[..bound].copy_from_slice(&values[..bound]);
+        self.values[bound..].fill(0f64);
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        let mut y = [0f64];
+        if self.registers <= STACK_REGISTERS {
+            self.run(&[x], &mut y, &mut [0f64; STACK_REGISTERS]);
+        } else {
+            self.eval_many(&[x], &mut y);
+        }
+        y[0]
+    }
+
+    //only as many samples as both xs and out have room for are evaluated, the rest of out is left alone
+    pub fn eval_many(&self, xs: &[f64], out: &mut [f64]) {
+        let len = xs.len().min(out.len());
+        let (xs, out) = (&xs[..len], &mut out[..len]);
+        let mut registers = vec![0f64; self.registers * LANES.min(xs.len())];
+        for (xs, out) in xs.chunks(LANES).zip(out.chunks_mut(LANES)) {
+            self.run(xs, out, &mut registers);
+        }
+    }
+
+    //register r of this chunk lives at r * xs.len()
+    fn run(&self, xs: &[f64], out: &mut [f64], registers: &mut [f64]) {
+        let lanes = xs.len();
+        let register = |r: usize| r * lanes..(r + 1) * lanes;
+        for instruction in &self.code {
+            match *instruction {
+                Instruction::Constant { out, value } => registers[register(out)].fill(value),
+                Instruction::Argument { out } => registers[register(out)].copy_from_slice(xs),
+                Instruction::Parameter { out, index } => registers[register(out)].fill(self.values[index]),
+                Instruction::Negate { a } => registers[register(a)].iter_mut().for_each(|a| *a = -*a),
+                Instruction::Unary { function, a } => {
+                    registers[register(a)].iter_mut().for_each(|a| *a = function(*a))
+                }
+                Instruction::Binary { op, a, b } => {
+                    let (a, b) = operands(registers, register(a), register(b));
+                    match op {
+                        BinaryOp::Add => a.iter_mut().zip(b).for_each(|(a, b)| *a += b),
+                        BinaryOp::Subtract => a.iter_mut().zip(b).for_each(|(a, b)| *a -= b),
+                        BinaryOp::Multiply => a.iter_mut().zip(b).for_each(|(a, b)| *a *= b),
+                        BinaryOp::Divide => a.iter_mut().zip(b).for_each(|(a, b)| *a /= b),
+                        BinaryOp::Power => a.iter_mut().zip(b).for_each(|(a, b)| *a = a.powf(*b)),
+                    }
+                }
+                Instruction::PowI { a, n } => registers[register(a)].iter_mut().for_each(|a| *a = a.powi(n)),
+                Instruction::Min { a, b } => {
+                    let (a, b) = operands(registers, register(a), register(b));
+                    a.iter_mut().zip(b).for_each(|(a, b)| *a = a.min(*b));
+                }
+                Instruction::Max { a, b } => {
+                    let (a, b) = operands(registers, register(a), register(b));
+                    a.iter_mut().zip(b).for_each(|(a, b)| *a = a.max(*b));
+                }
+            }
+        }
+        out.copy_from_slice(&registers[register(0)]);
+    }
+
+    //leave the value of expr in register out, using only the registers above it as scratch
+    fn emit(&mut self, expr: &Expr, out: usize) {
+        self.registers = self.registers.max(out + 1);
+        if let Some(value) = constant(expr) {
+            self.code.push(Instruction::Constant { out, value });
+            return;
+        }
+        match expr {
+            Expr::Number(value) => self.code.push(Instruction::Constant { out, value: *value }),
+            Expr::Argument(_) => self.code.push(Instruction::Argument { out }),
+            Expr::Parameter(index) => self.code.push(Instruction::Parameter { out, index: *index }),
+            Expr::Negate(a) => {
+                self.emit(a, out);
+                self.code.push(Instruction::Negate { a: out });
+            }
+            Expr::Binary(op, a, b) => {
+                self.emit(a, out);
+                match whole_exponent(b).filter(|_| *op == BinaryOp::Power) {
+                    Some(n) => self.code.push(Instruction::PowI { a: out, n }),
+                    None => {
+                        self.emit(b, out + 1);
+                        self.code.push(Instruction::Binary {
+                            op: *op,
+                            a: out,
+                            b: out + 1,
+                        });
+                    }
+                }
+            }
+            Expr::Call(function, args) => {
+                for (i, arg) in args.iter().enumerate() {
+                    self.emit(arg, out + i);
+                }
+                let instruction = match function {
+                    Function::Min => Instruction::Min { a: out, b: out + 1 },
+                    Function::Max => Instruction::Max { a: out, b: out + 1 },
+                    _ => Instruction::Unary {
+                        function: unary(*function),
+                        a: out,
+                    },
+                };
+                self.code.push(instruction);
+            }
+            //never linked, handled by constant
+            Expr::Apply(..) => unreachable!(),
+        }
+    }
+}
+
+//the value of a part of the tree that reads neither x nor a parameter
+fn constant(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Number(value) => Some(*value),
+        Expr::Argument(_) | Expr::Parameter(_) => None,
+        Expr::Negate(a) => constant(a).map(|a| -a),
+        Expr::Binary(op, a, b) => Some(op.apply(constant(a)?, constant(b)?)),
+        Expr::Call(function, args) => {
+            let args: Option<Vec<f64>> = args.iter().map(constant).collect();
+            Some(function.apply(&args?))
+        }
+        Expr::Apply(..) => Some(f64::NAN),
+    }
+}
+
+//exponents like the 2 in x^2
+fn whole_exponent(expr: &Expr) -> Option<i32> {
+    match constant(expr) {
+        Some(n) if n.fract() == 0f64 && n.abs() <= 64f64 => Some(n as i32),
+        _ => None,
+    }
+}
+
+//the built in functions that take one argument
+fn unary(function: Function) -> fn(f64) -> f64 {
+    match function {
+        Function::Sin => f64::sin,
+        Function::Cos => f64::cos,
+        Function::Tan => f64::tan,
+        Function::Asin => f64::asin,
+        Function::Acos => f64::acos,
+        Function::Atan => f64::atan,
+        Function::Sinh => f64::sinh,
+        Function::Cosh => f64::cosh,
+        Function::Tanh => f64::tanh,
+        Function::Sqrt => f64::sqrt,
+        Function::Exp => f64::exp,
+        Function::Ln => f64::ln,
+        Function::Log => f64::log10,
+        Function::Abs => f64::abs,
+        Function::Floor => f64::floor,
+        Function::Ceil => f64::ceil,
+        Function::Min | Function::Max => unreachable!("{:?} takes two arguments", function),
+    }
+}
+
+//the first operand to write into and the second to read from, b is always above a
+fn operands(
+    registers: &mut [f64],
+    a: std::ops::Range<usize>,
+    b: std::ops::Range<usize>,
+) -> (&mut [f64], &[f64]) {
+    let (low, high) = registers.split_at_mut(b.start);
+    (&mut low[a], &high[..b.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xs(count: usize) -> Vec<f64> {
+        (0..count).map(|i| -5f64 + 10f64 * i as f64 / count as f64).collect()
+    }
+
+    //NaN where the tree gives NaN, otherwise as close as powi and powf allow
+    fn assert_same(compiled: f64, walked: f64, x: f64) {
+        let same = (compiled.is_nan() && walked.is_nan())
+            || compiled == walked
+            || (compiled - walked).abs() <= 1e-12 * walked.abs().max(1f64);
+        assert!(same, "{} at x = {}, walking the tree gives {}", compiled, x, walked);
+    }
+
+    //eval and eval_many both agree with walking the tree at every x
+    fn check(source: &str, values: &[f64], xs: &[f64]) -> Program {
+        let expression = Expression::parse(source).unwrap();
+        let mut program = expression.compile();
+        program.bind(values);
+
+        let mut out = vec![0f64; xs.len()];
+        program.eval_many(xs, &mut out);
+        for (&x, &y) in xs.iter().zip(&out) {
+            let walked = expression.eval(x, values);
+            assert_same(program.eval(x), walked, x);
+            assert_same(y, walked, x);
+        }
+        program
+    }
+
+    #[test]
+    fn matches_walking_the_tree() {
+        for source in [
+            "x",
+            "-x^2 + 3x - 1",
+            "2^x^2",
+            "sin(x) * cos(2x) / (1 + x^2)",
+            "exp(-x^2 / 2) + abs(floor(x) - ceil(x))",
+            "min(x, 1) - max(x^3, -x)",
+            "tanh(x) + atan(x) + sinh(x / 4) - cosh(x / 4)",
+        ] {
+            check(source, &[], &xs(100));
+        }
+    }
+
+    fn uses_powi(program: &Program) -> bool {
+        let powi = |instruction: &Instruction| matches!(instruction, Instruction::PowI { .. });
+        program.code.iter().any(powi)
+    }
+
+    #[test]
+    fn whole_exponents_use_powi() {
+        for source in ["x^2", "x^-3", "(x + 1)^10", "x^(1 + 1)"] {
+            let program = check(source, &[], &xs(100));
+            assert!(uses_powi(&program), "{}", source);
+        }
+        let program = check("x^0.5", &[], &xs(100));
+        assert!(!uses_powi(&program));
+    }
+
+    #[test]
+    fn constant_parts_are_folded() {
+        let program = check("2 * pi + sin(1) - e^2", &[], &xs(10));
+        assert_eq!(program.code.len(), 1);
+        let program = check("x * (2 * pi)", &[], &xs(10));
+        assert_eq!(program.code.len(), 3);
+    }
+
+    #[test]
+    fn parameters_are_read_from_what_was_bound() {
+        check("a*x^2 + b*x + c", &[2f64, -1f64, 0.5], &xs(100));
+        let program = check("a*sin(b*x)", &[0f64, 0f64], &xs(10));
+        assert_eq!(program.eval(1f64), 0f64);
+    }
+
+    #[test]
+    fn binding_the_wrong_number_of_values() {
+        let mut program = Expression::parse("a*x + b").unwrap().compile();
+        program.bind(&[1f64, 2f64, 3f64]);
+        assert_eq!(program.eval(2f64), 4f64);
+        //b goes back to zero
+        program.bind(&[1f64]);
+        assert_eq!(program.eval(2f64), 2f64);
+        program.bind(&[]);
+        assert_eq!(program.eval(2f64), 0f64);
+    }
+
+    #[test]
+    fn mismatched_outputs_evaluate_what_fits() {
+        let program = Expression::parse("x + 1").unwrap().compile();
+        let mut out = [0f64; 2];
+        program.eval_many(&[1f64, 2f64, 3f64], &mut out);
+        assert_eq!(out, [2f64, 3f64]);
+
+        let mut out = [-1f64; 4];
+        program.eval_many(&[1f64, 2f64], &mut out);
+        assert_eq!(out, [2f64, 3f64, -1f64, -1f64]);
+    }
+
+    #[test]
+    fn batches_longer_than_the_lanes() {
+        for count in [LANES - 1, LANES, LANES + 1, 3 * LANES + 17] {
+            check("x^3 - 2x + a", &[1.5], &xs(count));
+        }
+    }
+
+    #[test]
+    fn programs_with_more_registers_than_the_stack_holds() {
+        //every operand on the right takes one more register
+        let nested: String = (0..24).map(|i| format!("x * {} + (", i)).collect();
+        let nested = nested + "x" + &")".repeat(24);
+        let program = check(&nested, &[], &xs(3 * LANES + 5));
+        assert!(program.registers > STACK_REGISTERS);
+    }
+
+    #[test]
+    fn undefined_samples_are_nan() {
+        let xs = [-1f64, 0f64, 1f64, f64::NAN, f64::INFINITY];
+        let sources = ["sqrt(x)", "ln(x)", "0 / x", "x / 0", "asin(x * 2)", "(-1)^(x / 2)", "x - x"];
+        for source in sources {
+            check(source, &[], &xs);
+        }
+        let program = check("sqrt(x) + 1", &[], &xs);
+        assert!(program.eval(-1f64).is_nan());
+    }
+}
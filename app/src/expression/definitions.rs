@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{DefinitionError, Expr, Expression, Function, ParseError};
+
+//linked expressions are refused past this many terms, definitions that each call the one before
+//several times would otherwise write out exponentially many
+const MAX_LINKED_SIZE: usize = 10_000;
+
+//a named function typed by the user, like g(x) = x^2 + 1
+//the body can use its arguments, free parameters and other definitions
+#[derive(Debug, Clone)]
+pub struct Definition {
+    name: String,
+    arguments: Vec<String>,
+    body: Expression,
+}
+
+impl Definition {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let invalid = || ParseError::InvalidDefinition(source.trim().to_string());
+        let (head, body) = source.split_once('=').ok_or_else(invalid)?;
+        let (name, arguments) = head
+            .trim()
+            .strip_suffix(')')
+            .and_then(|head| head.split_once('('))
+            .ok_or_else(invalid)?;
+
+        let name = name.trim().to_string();
+        let arguments: Vec<String> = arguments
+            .split(',')
+            .map(|argument| argument.trim().to_string())
+            .collect();
+        if !is_identifier(&name) || !arguments.iter().all(|argument| is_identifier(argument)) {
+            return Err(invalid());
+        }
+        for (i, argument) in arguments.iter().enumerate() {
+            if matches!(argument.as_str(), "pi" | "e") || Function::from_name(argument).is_some() {
+                return Err(ParseError::ReservedArgument(argument.clone()));
+            }
+            if arguments[..i].contains(argument) {
+                return Err(ParseError::DuplicateArgument(argument.clone()));
+            }
+        }
+
+        //errors in the body point into the whole definition
+        let body = Expression::parse_with_arguments(body, &arguments)
+            .map_err(|e| e.offset(head.len() + 1))?;
+        Ok(Self {
+            name,
+            arguments,
+            body,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arguments(&self) -> &[String] {
+        &self.arguments
+    }
+
+    pub fn body(&self) -> &Expression {
+        &self.body
+    }
+
+    //written back out the way it would be typed
+    pub fn source(&self) -> String {
+        format!(
+            "{}({}) = {}",
+            self.name,
+            self.arguments.join(", "),
+            self.body.source()
+        )
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+//every user defined function, kept free of cycles so linking always ends
+//each definition carries a revision that changes whenever it is redefined, expressions remember
+//the revisions they were linked against so only the ones depending on a change are linked again
+#[derive(Default)]
+pub struct Definitions {
+    definitions: BTreeMap<String, (Definition, u64)>,
+    last_revision: u64,
+}
+
+impl Definitions {
+    //parse and add or replace a definition, giving back its name
+    pub fn define(&mut self, source: &str) -> Result<String, DefinitionError> {
+        let definition = Definition::parse(source)?;
+        let name = definition.name.clone();
+        self.insert(definition)?;
+        Ok(name)
+    }
+
+    //the definitions are left as they were if the new one is refused
+    pub fn insert(&mut self, definition: Definition) -> Result<(), DefinitionError> {
+        let name = definition.name.as_str();
+        if matches!(name, "x" | "pi" | "e") || Function::from_name(name).is_some() {
+            return Err(DefinitionError::Reserved(name.to_string()));
+        }
+        if let Some(cycle) = self.find_cycle(&definition) {
+            return Err(DefinitionError::Cycle(cycle));
+        }
+
+        //defining the same thing again isn't a change
+        if self
+            .get(name)
+            .is_some_and(|old| old.source() == definition.source())
+        {
+            return Ok(());
+        }
+        self.last_revision += 1;
+        self.definitions
+            .insert(name.to_string(), (definition, self.last_revision));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Definition> {
+        self.definitions.remove(name).map(|(definition, _)| definition)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Definition> {
+        self.definitions.get(name).map(|(definition, _)| definition)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Definition> {
+        self.definitions.values().map(|(definition, _)| definition)
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    //0 for names that aren't defined, so defining one later counts as a change
+    pub fn revision(&self, name: &str) -> u64 {
+        self.definitions.get(name).map_or(0, |(_, revision)| *revision)
+    }
+
+    //whether anything linked against these revisions has to be linked again
+    pub fn is_stale(&self, links: &[(String, u64)]) -> bool {
+        links
+            .iter()
+            .any(|(name, revision)| self.revision(name) != *revision)
+    }
+
+    //replace every call to a definition with the definition's body, giving an expression
+    //in x that doesn't need the definitions to evaluate
+    //the parameters of the definitions used are added after the expression's own, and every definition
+    //used, directly or not, is added to links with its revision, even when linking fails
+    pub fn link(
+        &self,
+        expression: &Expression,
+        links: &mut Vec<(String, u64)>,
+    ) -> Result<Expression, DefinitionError> {
+        let mut linker = Linker {
+            definitions: self,
+            parameters: expression.parameters().to_vec(),
+            links,
+            size: 0,
+        };
+        let ast = linker.inline(expression.ast(), expression, &[Expr::Argument(0)])?;
+        Ok(Expression {
+            source: expression.source().to_string(),
+            ast,
+            parameters: linker.parameters,
+            functions: Vec::new(),
+        })
+    }
+
+    //the calls that lead from the definition back to itself, if there are any
+    fn find_cycle(&self, definition: &Definition) -> Option<Vec<String>> {
+        let mut path = vec![definition.name.clone()];
+        let mut visited = BTreeSet::new();
+        self.visit(definition, &definition.name, &mut path, &mut visited)
+            .then_some(path)
+    }
+
+    //the rest of the definitions never call each other in a cycle, so this only has to look for the new one
+    fn visit<'a>(
+        &'a self,
+        caller: &'a Definition,
+        target: &str,
+        path: &mut Vec<String>,
+        visited: &mut BTreeSet<&'a str>,
+    ) -> bool {
+        for name in caller.body.functions() {
+            path.push(name.clone());
+            if name == target {
+                return true;
+            }
+            if visited.insert(name.as_str()) {
+                if let Some(callee) = self.get(name) {
+                    if self.visit(callee, target, path, visited) {
+                        return true;
+                    }
+                }
+            }
+            path.pop();
+        }
+        false
+    }
+}
+
+struct Linker<'a> {
+    definitions: &'a Definitions,
+    parameters: Vec<String>,
+    links: &'a mut Vec<(String, u64)>,
+    //how many terms have been written out so far, see MAX_LINKED_SIZE
+    size: usize,
+}
+
+impl<'a> Linker<'a> {
+    //owner is the expression the tree came from, for its parameter and function names
+    //and arguments are what its arguments stand for in the linked tree
+    fn inline(
+        &mut self,
+        expr: &Expr,
+        owner: &Expression,
+        arguments: &[Expr],
+    ) -> Result<Expr, DefinitionError> {
+        //arguments are copied in whole wherever they're used
+        self.size += match expr {
+            Expr::Argument(i) => size(&arguments[*i]),
+            _ => 1,
+        };
+        if self.size > MAX_LINKED_SIZE {
+            return Err(DefinitionError::TooLarge(MAX_LINKED_SIZE));
+        }
+
+        Ok(match expr {
+            Expr::Number(n) => Expr::Number(*n),
+            Expr::Argument(i) => arguments[*i].clone(),
+            Expr::Parameter(i) => {
+                let name = &owner.parameters()[*i];
+                let index = match self.parameters.iter().position(|parameter| parameter == name) {
+                    Some(index) => index,
+                    None => {
+                        self.parameters.push(name.clone());
+                        self.parameters.len() - 1
+                    }
+                };
+                Expr::Parameter(index)
+            }
+            Expr::Negate(a) => Expr::Negate(Box::new(self.inline(a, owner, arguments)?)),
+            Expr::Binary(op, a, b) => Expr::Binary(
+                *op,
+                Box::new(self.inline(a, owner, arguments)?),
+                Box::new(self.inline(b, owner, arguments)?),
+            ),
+            Expr::Call(function, args) => Expr::Call(*function, self.inline_all(args, owner, arguments)?),
+            Expr::Apply(i, args) => {
+                let name = &owner.functions()[*i];
+                if !self.links.iter().any(|(linked, _)| linked == name) {
+                    self.links.push((name.clone(), self.definitions.revision(name)));
+                }
+
+                let definitions = self.definitions;
+                let definition = definitions
+                    .get(name)
+                    .ok_or_else(|| DefinitionError::Undefined(name.clone()))?;
+                if args.len() != definition.arguments.len() {
+                    return Err(DefinitionError::WrongArgumentCount {
+                        function: name.clone(),
+                        expected: definition.arguments.len(),
+                        found: args.len(),
+                    });
+                }
+                let args = self.inline_all(args, owner, arguments)?;
+                self.inline(definition.body.ast(), &definition.body, &args)?
+            }
+        })
+    }
+
+    fn inline_all(
+        &mut self,
+        exprs: &[Expr],
+        owner: &Expression,
+        arguments: &[Expr],
+    ) -> Result<Vec<Expr>, DefinitionError> {
+        exprs
+            .iter()
+            .map(|expr| self.inline(expr, owner, arguments))
+            .collect()
+    }
+}
+
+//how many terms are in the tree
+fn size(expr: &Expr) -> usize {
+    1 + match expr {
+        Expr::Number(_) | Expr::Argument(_) | Expr::Parameter(_) => 0,
+        Expr::Negate(a) => size(a),
+        Expr::Binary(_, a, b) => size(a) + size(b),
+        Expr::Call(_, args) | Expr::Apply(_, args) => args.iter().map(size).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definitions(sources: &[&str]) -> Definitions {
+        let mut definitions = Definitions::default();
+        for source in sources {
+            definitions.define(source).unwrap();
+        }
+        definitions
+    }
+
+    fn link(
+        definitions: &Definitions,
+        source: &str,
+    ) -> (Result<Expression, DefinitionError>, Vec<(String, u64)>) {
+        let mut links = Vec::new();
+        let linked = definitions.link(&Expression::parse(source).unwrap(), &mut links);
+        (linked, links)
+    }
+
+    #[test]
+    fn linking_inlines_definitions_and_their_parameters() {
+        let definitions = definitions(&["g(t) = t^2 + a", "h(x) = g(x + 1) * b"]);
+        let (linked, links) = link(&definitions, "h(x) + c");
+        let linked = linked.unwrap();
+        //the expression's own parameters come first
+        assert_eq!(linked.parameters(), ["c", "a", "b"]);
+        assert!(linked.functions().is_empty());
+        assert_eq!(linked.eval(1f64, &[1f64, 2f64, 3f64]), 19f64);
+        let mut names: Vec<&str> = links.iter().map(|(name, _)| name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["g", "h"]);
+    }
+
+    #[test]
+    fn direct_cycles_are_refused() {
+        let mut definitions = definitions(&["f(x) = x + 1"]);
+        let revision = definitions.revision("f");
+        assert_eq!(
+            definitions.define("f(x) = f(x) + 1"),
+            Err(DefinitionError::Cycle(vec!["f".to_string(), "f".to_string()]))
+        );
+        assert_eq!(definitions.get("f").unwrap().source(), "f(x) = x + 1");
+        assert_eq!(definitions.revision("f"), revision);
+    }
+
+    #[test]
+    fn indirect_cycles_are_refused() {
+        let mut definitions = definitions(&["a(x) = b(x) + 1", "b(x) = c(x) * 2", "c(x) = x"]);
+        assert_eq!(
+            definitions.define("c(x) = a(x)"),
+            Err(DefinitionError::Cycle(
+                ["c", "a", "b", "c"].iter().map(|name| name.to_string()).collect()
+            ))
+        );
+        assert_eq!(definitions.get("c").unwrap().source(), "c(x) = x");
+        assert_eq!(definitions.len(), 3);
+        let (linked, _) = link(&definitions, "a(x)");
+        assert_eq!(linked.unwrap().eval(3f64, &[]), 7f64);
+    }
+
+    #[test]
+    fn redefining_only_changes_the_revisions_of_dependents() {
+        let mut definitions = definitions(&["g(x) = x^2", "h(x) = g(x) + 1", "k(x) = 2x"]);
+        let (_, h_links) = link(&definitions, "h(x)");
+        let (_, k_links) = link(&definitions, "k(x)");
+        assert!(!definitions.is_stale(&h_links));
+
+        //the same source again isn't a change
+        definitions.define("g(x) = x^2").unwrap();
+        assert!(!definitions.is_stale(&h_links));
+
+        definitions.define("g(x) = x^3").unwrap();
+        assert!(definitions.is_stale(&h_links));
+        assert!(!definitions.is_stale(&k_links));
+    }
+
+    #[test]
+    fn undefined_names_are_linked_again_once_defined() {
+        let mut definitions = Definitions::default();
+        let (linked, links) = link(&definitions, "q(x) + 1");
+        assert_eq!(linked.unwrap_err(), DefinitionError::Undefined("q".to_string()));
+        assert!(!definitions.is_stale(&links));
+
+        definitions.define("q(x) = x").unwrap();
+        assert!(definitions.is_stale(&links));
+    }
+
+    #[test]
+    fn calls_need_the_right_number_of_arguments() {
+        let definitions = definitions(&["g(x, y) = x * y"]);
+        let (linked, _) = link(&definitions, "g(x)");
+        assert_eq!(
+            linked.unwrap_err(),
+            DefinitionError::WrongArgumentCount {
+                function: "g".to_string(),
+                expected: 2,
+                found: 1,
+            }
+        );
+        let (linked, _) = link(&definitions, "g(x, 3)");
+        assert_eq!(linked.unwrap().eval(2f64, &[]), 6f64);
+    }
+
+    #[test]
+    fn built_in_names_are_reserved() {
+        let mut definitions = Definitions::default();
+        for name in ["x", "pi", "e", "sin"] {
+            let source = format!("{}(t) = t", name);
+            assert_eq!(definitions.define(&source), Err(DefinitionError::Reserved(name.to_string())));
+        }
+        assert!(definitions.is_empty());
+    }
+
+    #[test]
+    fn arguments_are_checked() {
+        assert_eq!(
+            Definition::parse("g(x, x) = x").unwrap_err(),
+            ParseError::DuplicateArgument("x".to_string())
+        );
+        for name in ["pi", "e", "sin"] {
+            let source = format!("g({}) = 1", name);
+            assert_eq!(
+                Definition::parse(&source).unwrap_err(),
+                ParseError::ReservedArgument(name.to_string())
+            );
+        }
+        //x is only the variable when it's an argument, otherwise it's a mistake rather than a parameter
+        assert_eq!(
+            Definition::parse("g(t) = t + x").unwrap_err(),
+            ParseError::NotAnArgument(11, "x".to_string())
+        );
+        assert_eq!(Definition::parse("g(t) = t + a").unwrap().body().parameters(), ["a"]);
+    }
+
+    #[test]
+    fn linking_stops_before_writing_out_too_much() {
+        let mut sources = vec!["g0(x) = x".to_string()];
+        sources.extend((1..=20).map(|i| format!("g{}(x) = g{}(x) + g{}(x)", i, i - 1, i - 1)));
+        let mut definitions = Definitions::default();
+        for source in &sources {
+            definitions.define(source).unwrap();
+        }
+
+        let (linked, _) = link(&definitions, "g20(x)");
+        assert_eq!(linked.unwrap_err(), DefinitionError::TooLarge(MAX_LINKED_SIZE));
+        let (linked, _) = link(&definitions, "g5(x)");
+        assert_eq!(linked.unwrap().eval(1f64, &[]), 32f64);
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+
+//what went wrong reading an expression, positions are byte offsets into the source
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedChar(usize, char),
+    UnexpectedToken(usize, String),
+    UnexpectedEnd,
+    //a definition has to look like name(x) = body
+    InvalidDefinition(String),
+    //a definition naming the same argument twice, like g(x, x)
+    DuplicateArgument(String),
+    //a definition's argument named after a constant or a built in function
+    ReservedArgument(String),
+    //x used in a definition that doesn't take it as an argument
+    NotAnArgument(usize, String),
+    //a built in function called with the wrong number of arguments
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    //brackets, calls, negations or powers nested more than this many levels deep
+    TooDeep(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedChar(position, c) => write!(f, "unexpected '{}' at {}", c, position),
+            Self::UnexpectedToken(position, token) => write!(f, "unexpected '{}' at {}", token, position),
+            Self::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            Self::InvalidDefinition(source) => {
+                write!(f, "{} isn't a definition, they look like g(x) = x^2 + 1", source)
+            }
+            Self::DuplicateArgument(name) => write!(f, "{} is an argument more than once", name),
+            Self::ReservedArgument(name) => write!(f, "{} is built in and can't be an argument", name),
+            Self::NotAnArgument(position, name) => {
+                write!(f, "{} at {} isn't one of the definition's arguments", name, position)
+            }
+            Self::TooDeep(limit) => write!(f, "nested more than {} levels deep", limit),
+            Self::WrongArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} argument(s) but was given {}",
+                function, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    //the same error for a source starting offset bytes further in, like a definition's body
+    pub(super) fn offset(self, offset: usize) -> Self {
+        match self {
+            Self::UnexpectedChar(position, c) => Self::UnexpectedChar(position + offset, c),
+            Self::UnexpectedToken(position, token) => Self::UnexpectedToken(position + offset, token),
+            Self::NotAnArgument(position, name) => Self::NotAnArgument(position + offset, name),
+            e => e,
+        }
+    }
+}
+
+//what went wrong defining a function or linking an expression against the definitions
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefinitionError {
+    Parse(ParseError),
+    //the name belongs to a built in function, a constant or x
+    Reserved(String),
+    //the definitions would call each other forever, in the order they call each other,
+    //ending where it started
+    Cycle(Vec<String>),
+    Undefined(String),
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    //writing out the definitions called would take more than this many terms
+    TooLarge(usize),
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "{}", e),
+            Self::Reserved(name) => write!(f, "{} is built in and can't be redefined", name),
+            Self::Cycle(names) => write!(f, "definitions call each other forever: {}", names.join(" -> ")),
+            Self::Undefined(name) => write!(f, "{} isn't defined", name),
+            Self::TooLarge(limit) => write!(
+                f,
+                "writing out the definitions it calls takes more than {} terms",
+                limit
+            ),
+            Self::WrongArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} argument(s) but was given {}",
+                function, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+impl From<ParseError> for DefinitionError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
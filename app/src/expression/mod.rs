@@ -0,0 +1,83 @@
+mod ast;
+mod compile;
+mod definitions;
+mod error;
+mod parser;
+
+use std::{fmt, str::FromStr};
+
+pub use ast::*;
+pub use compile::Program;
+pub use definitions::*;
+pub use error::*;
+
+use parser::Parser;
+
+//a formula in x typed by the user, like a*sin(b*x + c)
+//any name that isn't x, pi, e or a function is a free parameter whose value is given when evaluating
+//calls to functions that aren't built in are looked up in the Definitions when linking
+#[derive(Debug, Clone)]
+pub struct Expression {
+    source: String,
+    ast: Expr,
+    parameters: Vec<String>,
+    functions: Vec<String>,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        Self::parse_with_arguments(source, &["x".to_string()])
+    }
+
+    fn parse_with_arguments(source: &str, arguments: &[String]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(source, arguments)?;
+        let ast = parser.parse()?;
+        Ok(Self {
+            source: source.trim().to_string(),
+            ast,
+            parameters: parser.parameters,
+            functions: parser.functions,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn ast(&self) -> &Expr {
+        &self.ast
+    }
+
+    //the free parameters in the order they first appear, values are passed to eval in this order
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    //the user defined functions called directly, in the order they first appear
+    pub fn functions(&self) -> &[String] {
+        &self.functions
+    }
+
+    //walks the tree, compile it for evaluating at many samples
+    pub fn eval(&self, x: f64, values: &[f64]) -> f64 {
+        self.ast.eval(x, values)
+    }
+
+    pub fn compile(&self) -> Program {
+        Program::compile(self)
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
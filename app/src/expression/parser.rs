@@ -0,0 +1,368 @@
+use super::{BinaryOp, Expr, Function, ParseError};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Self::Number(n) => n.to_string(),
+            Self::Identifier(name) => name.clone(),
+            Self::Operator(c) => c.to_string(),
+            Self::LeftParen => "(".to_string(),
+            Self::RightParen => ")".to_string(),
+            Self::Comma => ",".to_string(),
+        }
+    }
+}
+
+//split the source into tokens along with where each one starts
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !c.is_ascii_digit() && c != '.' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = source[start..end]
+                .parse()
+                .map_err(|_| ParseError::UnexpectedToken(start, source[start..end].to_string()))?;
+            tokens.push((start, Token::Number(number)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push((start, Token::Identifier(source[start..end].to_string())));
+        } else {
+            let token = match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Operator(c),
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                ',' => Token::Comma,
+                _ => return Err(ParseError::UnexpectedChar(start, c)),
+            };
+            tokens.push((start, token));
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+//how deep brackets, calls, negations and powers may nest, deeper sources are refused rather than
+//overflowing the stack while they're parsed, compiled or evaluated
+const MAX_DEPTH: usize = 100;
+
+//a recursive descent parser, from loosest to tightest binding:
+//  sum     = product (('+' | '-') product)*
+//  product = unary (('*' | '/') unary | unary)*   two terms side by side multiply, 2x or a sin(x)
+//  unary   = '-' unary | power
+//  power   = primary ('^' unary)?                 right associative, so -x^2 is -(x^2)
+//  primary = number | name | name '(' sum (',' sum)* ')' | '(' sum ')'
+pub(super) struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    //how many unary calls are under way, see MAX_DEPTH
+    depth: usize,
+    //the names that stand for the arguments, just x unless this is a definition's body
+    arguments: &'a [String],
+    //every name that isn't an argument, a constant or a function, in the order first seen
+    pub(super) parameters: Vec<String>,
+    //every function called that isn't built in, in the order first seen
+    pub(super) functions: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    pub(super) fn new(source: &str, arguments: &'a [String]) -> Result<Self, ParseError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            next: 0,
+            depth: 0,
+            arguments,
+            parameters: Vec::new(),
+            functions: Vec::new(),
+        })
+    }
+
+    //the whole source has to be one expression
+    pub(super) fn parse(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.sum()?;
+        match self.tokens.get(self.next) {
+            Some((position, token)) => Err(ParseError::UnexpectedToken(*position, token.text())),
+            None => Ok(expr),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn advance(&mut self) -> Result<(usize, Token), ParseError> {
+        let token = self.tokens.get(self.next).cloned().ok_or(ParseError::UnexpectedEnd)?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
+        match self.advance()? {
+            (_, token) if token == expected => Ok(()),
+            (position, token) => Err(ParseError::UnexpectedToken(position, token.text())),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator('+')) => BinaryOp::Add,
+                Some(Token::Operator('-')) => BinaryOp::Subtract,
+                _ => return Ok(expr),
+            };
+            self.next += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator('*')) => BinaryOp::Multiply,
+                Some(Token::Operator('/')) => BinaryOp::Divide,
+                Some(Token::Number(_) | Token::Identifier(_) | Token::LeftParen) => {
+                    expr = Expr::Binary(BinaryOp::Multiply, Box::new(expr), Box::new(self.power()?));
+                    continue;
+                }
+                _ => return Ok(expr),
+            };
+            self.next += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    //every level of nesting comes back through here, so this is where the depth is counted
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError::TooDeep(MAX_DEPTH));
+        }
+        self.depth += 1;
+        let expr = match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.next += 1;
+                self.unary().map(|expr| Expr::Negate(Box::new(expr)))
+            }
+            Some(Token::Operator('+')) => {
+                self.next += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        };
+        self.depth -= 1;
+        expr
+    }
+
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.primary()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.next += 1;
+            return Ok(Expr::Binary(BinaryOp::Power, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        match self.advance()? {
+            (_, Token::Number(n)) => Ok(Expr::Number(n)),
+            (_, Token::LeftParen) => {
+                let expr = self.sum()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            (_, Token::Identifier(name)) if self.peek() == Some(&Token::LeftParen) => self.call(name),
+            (position, Token::Identifier(name)) => self.name(position, name),
+            (position, token) => Err(ParseError::UnexpectedToken(position, token.text())),
+        }
+    }
+
+    fn call(&mut self, name: String) -> Result<Expr, ParseError> {
+        self.expect(Token::LeftParen)?;
+        let mut args = vec![self.sum()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.next += 1;
+            args.push(self.sum()?);
+        }
+        self.expect(Token::RightParen)?;
+
+        //anything that isn't built in is left for the definitions, which check the arguments when linking
+        let function = match Function::from_name(&name) {
+            Some(function) => function,
+            None => return Ok(Expr::Apply(index_of(&mut self.functions, name), args)),
+        };
+        if args.len() != function.arity() {
+            return Err(ParseError::WrongArgumentCount {
+                function: name,
+                expected: function.arity(),
+                found: args.len(),
+            });
+        }
+        Ok(Expr::Call(function, args))
+    }
+
+    //x is only ever the variable, a definition that doesn't take it can't read it as a parameter
+    fn name(&mut self, position: usize, name: String) -> Result<Expr, ParseError> {
+        if let Some(index) = self.arguments.iter().position(|argument| *argument == name) {
+            return Ok(Expr::Argument(index));
+        }
+        Ok(match name.as_str() {
+            "x" => return Err(ParseError::NotAnArgument(position, name)),
+            "pi" => Expr::Number(std::f64::consts::PI),
+            "e" => Expr::Number(std::f64::consts::E),
+            _ => Expr::Parameter(index_of(&mut self.parameters, name)),
+        })
+    }
+}
+
+//where a name is in the list, adding it to the end the first time it's seen
+fn index_of(names: &mut Vec<String>, name: String) -> usize {
+    match names.iter().position(|other| *other == name) {
+        Some(index) => index,
+        None => {
+            names.push(name);
+            names.len() - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser<'a>(source: &str, arguments: &'a [String]) -> (Result<Expr, ParseError>, Parser<'a>) {
+        let mut parser = Parser::new(source, arguments).unwrap();
+        (parser.parse(), parser)
+    }
+
+    fn parse(source: &str) -> Result<Expr, ParseError> {
+        Parser::new(source, &["x".to_string()])?.parse()
+    }
+
+    fn eval(source: &str, x: f64) -> f64 {
+        parse(source).unwrap().eval(x, &[])
+    }
+
+    #[test]
+    fn negation_binds_looser_than_powers() {
+        let power = Expr::Binary(BinaryOp::Power, Box::new(Expr::Argument(0)), Box::new(Expr::Number(2f64)));
+        assert_eq!(parse("-x^2"), Ok(Expr::Negate(Box::new(power))));
+        assert_eq!(eval("-x^2", 3f64), -9f64);
+        assert_eq!(eval("2^-1", 0f64), 0.5);
+    }
+
+    #[test]
+    fn powers_are_right_associative() {
+        assert_eq!(eval("2^3^2", 0f64), 512f64);
+        assert_eq!(eval("(2^3)^2", 0f64), 64f64);
+    }
+
+    #[test]
+    fn products_bind_tighter_than_sums() {
+        assert_eq!(eval("1 + 2 * 3", 0f64), 7f64);
+        assert_eq!(eval("8 / 4 / 2", 0f64), 1f64);
+        assert_eq!(eval("1 - 2 - 3", 0f64), -4f64);
+        //side by side terms multiply, as tightly as written out
+        assert_eq!(eval("2x^2", 3f64), 18f64);
+        assert_eq!(eval("1 / 2x", 4f64), 2f64);
+    }
+
+    #[test]
+    fn unknown_names_are_parameters_in_order() {
+        let arguments = ["x".to_string()];
+        let (expr, parser) = parser("a*sin(b*x + c) + a", &arguments);
+        assert_eq!(parser.parameters, ["a", "b", "c"]);
+        let values = [2f64, 1f64, 0f64];
+        assert_eq!(expr.unwrap().eval(std::f64::consts::FRAC_PI_2, &values), 4f64);
+    }
+
+    #[test]
+    fn constants_are_not_parameters() {
+        let arguments = ["x".to_string()];
+        let (expr, parser) = parser("pi + e", &arguments);
+        assert!(parser.parameters.is_empty());
+        assert_eq!(expr.unwrap().eval(0f64, &[]), std::f64::consts::PI + std::f64::consts::E);
+    }
+
+    #[test]
+    fn unknown_calls_are_left_for_the_definitions() {
+        let arguments = ["x".to_string()];
+        let (expr, parser) = parser("g(x, 2) + h(x) + g(1, x)", &arguments);
+        assert!(expr.is_ok());
+        assert_eq!(parser.functions, ["g", "h"]);
+        assert!(parser.parameters.is_empty());
+    }
+
+    #[test]
+    fn built_in_functions_check_their_arguments() {
+        assert_eq!(eval("max(x, 2)", 1f64), 2f64);
+        assert_eq!(
+            parse("sin(x, 2)"),
+            Err(ParseError::WrongArgumentCount {
+                function: "sin".to_string(),
+                expected: 1,
+                found: 2,
+            })
+        );
+        assert_eq!(
+            parse("min(x)"),
+            Err(ParseError::WrongArgumentCount {
+                function: "min".to_string(),
+                expected: 2,
+                found: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        assert_eq!(parse("1 + * 2"), Err(ParseError::UnexpectedToken(4, "*".to_string())));
+        assert_eq!(parse("(x + 1))"), Err(ParseError::UnexpectedToken(7, ")".to_string())));
+        assert_eq!(parse("sin(x,)"), Err(ParseError::UnexpectedToken(6, ")".to_string())));
+        assert_eq!(parse("x # 2"), Err(ParseError::UnexpectedChar(2, '#')));
+        assert_eq!(parse("1.2.3"), Err(ParseError::UnexpectedToken(0, "1.2.3".to_string())));
+        assert_eq!(parse("(x + 1"), Err(ParseError::UnexpectedEnd));
+        assert_eq!(parse(""), Err(ParseError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        //the whole expression is one level, every bracket another
+        assert!(parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(parse(&nested(MAX_DEPTH)), Err(ParseError::TooDeep(MAX_DEPTH)));
+
+        //far past the limit is refused before the stack runs out
+        let too_deep = Err(ParseError::TooDeep(MAX_DEPTH));
+        assert_eq!(parse(&"(".repeat(100_000)), too_deep);
+        assert_eq!(parse(&format!("{}x", "-".repeat(100_000))), too_deep);
+        assert_eq!(parse(&format!("{}x", "sin(".repeat(100_000))), too_deep);
+        assert_eq!(parse(&format!("x{}", "^x".repeat(100_000))), too_deep);
+    }
+}
@@ -0,0 +1,438 @@
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+use two_dimensional::View;
+use winit::event::{ElementState, KeyboardInput, ModifiersState, WindowEvent};
+
+use super::{
+    add_label, FrameTime, KeyMap, NavigationAction, Overlay, OverlayLayer, Parameters, Redraw, Theme,
+};
+
+//how far the speed keys can go, each press doubles or halves it
+const MIN_SPEED: f32 = 1f32 / 16f32;
+const MAX_SPEED: f32 = 16f32;
+//seconds to sweep a whole range at normal speed
+const DEFAULT_PERIOD: f32 = 4f32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationMode {
+    //from min up to max once, then stop
+    Linear,
+    //back and forth between min and max
+    PingPong,
+    //from min up to max, then jump back to min
+    Loop,
+}
+
+impl AnimationMode {
+    //the order the mode key cycles through
+    pub fn next(self) -> Self {
+        match self {
+            Self::Linear => Self::PingPong,
+            Self::PingPong => Self::Loop,
+            Self::Loop => Self::Linear,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::PingPong => "ping pong",
+            Self::Loop => "loop",
+        }
+    }
+}
+
+//one parameter moving across the range of its slider
+//the position along the range is read back from the parameter every frame,
+//so dragging the slider while it plays moves the animation with it
+#[derive(Debug, Clone)]
+pub struct ParameterAnimation {
+    pub parameter: String,
+    pub mode: AnimationMode,
+    //seconds to sweep the whole range once at a speed of 1
+    pub period: f32,
+    //1 on the way up, -1 on the way back down in ping pong
+    direction: f32,
+}
+
+impl ParameterAnimation {
+    pub fn new(parameter: impl Into<String>, mode: AnimationMode) -> Self {
+        Self {
+            parameter: parameter.into(),
+            mode,
+            period: DEFAULT_PERIOD,
+            direction: 1f32,
+        }
+    }
+
+    //the position along the range elapsed seconds after starting from t, worked out in one go
+    //rather than frame by frame, so the same moment always gives exactly the same value
+    pub fn sample(&self, t: f32, elapsed: f32) -> f32 {
+        let t = t + self.direction * elapsed / self.period;
+        match self.mode {
+            AnimationMode::Linear => t.clamp(0f32, 1f32),
+            AnimationMode::Loop => t.rem_euclid(1f32),
+            AnimationMode::PingPong => {
+                let t = t.rem_euclid(2f32);
+                if t > 1f32 {
+                    2f32 - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+
+    //seconds until a loop or ping pong is back where it started, or a linear animation is done
+    fn cycle(&self) -> f32 {
+        match self.mode {
+            AnimationMode::PingPong => 2f32 * self.period,
+            AnimationMode::Linear | AnimationMode::Loop => self.period,
+        }
+    }
+
+    //the position along the range after dt seconds, from 0 at min to 1 at max,
+    //None once a linear animation has reached the end
+    fn advance(&mut self, t: f32, dt: f32) -> Option<f32> {
+        if self.mode == AnimationMode::Linear && t >= 1f32 {
+            return None;
+        }
+        let t = t + self.direction * dt / self.period;
+        match self.mode {
+            AnimationMode::Linear => Some(t.min(1f32)),
+            AnimationMode::Loop => Some(t.rem_euclid(1f32)),
+            AnimationMode::PingPong if t >= 1f32 => {
+                self.direction = -1f32;
+                Some(2f32 - t)
+            }
+            AnimationMode::PingPong if t <= 0f32 => {
+                self.direction = 1f32;
+                Some(-t)
+            }
+            AnimationMode::PingPong => Some(t),
+        }
+    }
+}
+
+//the timeline every parameter animation runs on, they all play, pause and change speed together
+#[derive(Clone)]
+pub struct Animations {
+    pub animations: Vec<ParameterAnimation>,
+    pub playing: bool,
+    //a multiplier on every animation's period
+    pub speed: f32,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self {
+            animations: Vec::new(),
+            playing: false,
+            speed: 1f32,
+        }
+    }
+}
+
+impl Animations {
+    //start animating a parameter, replacing any animation it already had
+    pub fn animate(&mut self, animation: ParameterAnimation) {
+        self.animations
+            .retain(|other| other.parameter != animation.parameter);
+        self.animations.push(animation);
+    }
+
+    //what play starts with when nothing was set up, every parameter going back and forth
+    pub fn animate_all(&mut self, parameters: &Parameters) {
+        for (name, _) in parameters.iter() {
+            self.animate(ParameterAnimation::new(name, AnimationMode::PingPong));
+        }
+    }
+
+    //the parameters elapsed seconds into the timeline, starting from the values in start
+    pub fn sample(&self, start: &Parameters, elapsed: f32) -> Parameters {
+        let mut parameters = start.clone();
+        for animation in &self.animations {
+            if let Some(parameter) = parameters.get_mut(&animation.parameter) {
+                let range = parameter.max - parameter.min;
+                if range <= 0f32 {
+                    continue;
+                }
+                let t = ((parameter.value - parameter.min) / range).clamp(0f32, 1f32);
+                parameter.value = parameter.min + animation.sample(t, elapsed * self.speed) * range;
+            }
+        }
+        parameters
+    }
+
+    //seconds until every animation has come back around, None when there is nothing to animate
+    pub fn duration(&self) -> Option<f32> {
+        let longest = self
+            .animations
+            .iter()
+            .map(ParameterAnimation::cycle)
+            .reduce(f32::max)?;
+        Some(longest / self.speed)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.playing && !self.animations.is_empty()
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn cycle_mode(&mut self) {
+        for animation in &mut self.animations {
+            animation.mode = animation.mode.next();
+            animation.direction = 1f32;
+        }
+    }
+}
+
+//space plays and pauses, animating every parameter the first time, the brackets change the speed
+//and m switches the mode of every animation
+pub fn animation_input(
+    mut events: EventReader<WindowEvent<'static>>,
+    mut modifiers: Local<ModifiersState>,
+    key_map: Res<KeyMap>,
+    mut parameters: ResMut<Parameters>,
+    mut animations: ResMut<Animations>,
+) {
+    for event in events.iter() {
+        let key = match event {
+            WindowEvent::ModifiersChanged(new_modifiers) => {
+                *modifiers = *new_modifiers;
+                continue;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => *key,
+            _ => continue,
+        };
+        if modifiers.ctrl() || modifiers.alt() || modifiers.logo() {
+            continue;
+        }
+
+        match key_map.action(key) {
+            Some(NavigationAction::PlayPause) if animations.playing => animations.playing = false,
+            Some(NavigationAction::PlayPause) => {
+                if animations.animations.is_empty() {
+                    animations.animate_all(&parameters);
+                }
+                //linear animations that already reached the end start over
+                for animation in &animations.animations {
+                    if let (AnimationMode::Linear, Some(parameter)) =
+                        (animation.mode, parameters.get_mut(&animation.parameter))
+                    {
+                        if parameter.value >= parameter.max {
+                            parameter.value = parameter.min;
+                        }
+                    }
+                }
+                animations.playing = true;
+            }
+            Some(NavigationAction::SpeedUp) => {
+                let speed = animations.speed * 2f32;
+                animations.set_speed(speed);
+            }
+            Some(NavigationAction::SlowDown) => {
+                let speed = animations.speed / 2f32;
+                animations.set_speed(speed);
+            }
+            Some(NavigationAction::CycleAnimationMode) => animations.cycle_mode(),
+            _ => {}
+        }
+    }
+}
+
+//move every animated parameter along its range, animations move smoothly instead of snapping to steps
+//the timeline stops by itself once only finished linear animations are left
+pub fn animate_parameters(
+    time: Res<FrameTime>,
+    mut animations: ResMut<Animations>,
+    mut parameters: ResMut<Parameters>,
+    mut redraw: ResMut<Redraw>,
+) {
+    if !animations.is_running() {
+        return;
+    }
+
+    let dt = time.delta * animations.speed;
+    let mut running = false;
+    for animation in &mut animations.animations {
+        let parameter = match parameters.get(&animation.parameter) {
+            Some(parameter) if parameter.max > parameter.min => parameter,
+            _ => continue,
+        };
+        let range = parameter.max - parameter.min;
+        let t = ((parameter.value - parameter.min) / range).clamp(0f32, 1f32);
+        let t = match animation.advance(t, dt) {
+            Some(t) => t,
+            None => continue,
+        };
+        running = true;
+
+        let value = parameter.min + t * range;
+        if value != parameter.value {
+            if let Some(parameter) = parameters.get_mut(&animation.parameter) {
+                parameter.value = value;
+            }
+        }
+    }
+
+    if running {
+        redraw.request();
+    } else {
+        animations.playing = false;
+    }
+}
+
+//the state of the timeline in the bottom left corner, while there is anything to animate
+pub fn update_timeline_overlay(
+    animations: Res<Animations>,
+    mut shown: Local<String>,
+    view: Res<View>,
+    theme: Res<Theme>,
+    mut overlay: ResMut<Overlay>,
+) {
+    //the timeline changes every frame while it plays, only redraw when the text does
+    let text = match animations.animations.first() {
+        Some(animation) => format!(
+            "{} {}x {}",
+            if animations.playing { "playing" } else { "paused" },
+            animations.speed,
+            animation.mode.name()
+        ),
+        None => String::new(),
+    };
+    if *shown == text && !view.is_changed() && !theme.is_changed() {
+        return;
+    }
+
+    let mut layer = OverlayLayer::default();
+    if !text.is_empty() {
+        let (_, height) = view.screen_size();
+        add_label(&mut layer, &text, (0f32, height), &view, &theme);
+    }
+    *shown = text;
+    overlay.timeline = layer;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Parameter;
+
+    const FPS: f32 = 30f32;
+
+    //a starts three quarters of the way up its range
+    fn start() -> Parameters {
+        let mut parameters = Parameters::default();
+        parameters.insert(
+            "a",
+            Parameter {
+                value: 1f32,
+                min: -2f32,
+                max: 2f32,
+                step: 0f32,
+            },
+        );
+        parameters
+    }
+
+    fn animations(mode: AnimationMode) -> Animations {
+        let mut animations = Animations::default();
+        animations.animate(ParameterAnimation::new("a", mode));
+        animations.set_speed(2f32);
+        animations
+    }
+
+    //the frames an export of the whole timeline draws, the last one lands on the duration
+    fn frames(animations: &Animations) -> usize {
+        (animations.duration().unwrap() * FPS).round() as usize
+    }
+
+    #[test]
+    fn frames_do_not_depend_on_the_order_they_are_sampled_in() {
+        let start = start();
+        for mode in [AnimationMode::Linear, AnimationMode::PingPong, AnimationMode::Loop] {
+            let animations = animations(mode);
+            let frames = frames(&animations);
+            let forward: Vec<Parameters> = (0..=frames)
+                .map(|n| animations.sample(&start, n as f32 / FPS))
+                .collect();
+            let mut backward: Vec<Parameters> = (0..=frames)
+                .rev()
+                .map(|n| animations.sample(&start, n as f32 / FPS))
+                .collect();
+            backward.reverse();
+            assert_eq!(forward, backward, "{:?}", mode);
+
+            let again = animations.sample(&start, frames as f32 / 2f32 / FPS);
+            assert_eq!(again, forward[frames / 2], "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn linear_reaches_the_end_at_the_duration() {
+        let mut start = start();
+        start.get_mut("a").unwrap().value = -2f32;
+        let animations = animations(AnimationMode::Linear);
+        let frames = frames(&animations);
+        assert_eq!(animations.duration(), Some(2f32));
+
+        let last = animations.sample(&start, frames as f32 / FPS);
+        assert_eq!(last.value("a"), 2f32);
+        let before = animations.sample(&start, (frames - 1) as f32 / FPS);
+        assert!(before.value("a") < 2f32);
+    }
+
+    #[test]
+    fn ping_pong_is_back_where_it_started_at_the_duration() {
+        let start = start();
+        let animations = animations(AnimationMode::PingPong);
+        assert_eq!(animations.duration(), Some(4f32));
+
+        let halfway = animations.sample(&start, animations.duration().unwrap() / 2f32);
+        assert_eq!(halfway.value("a"), -1f32);
+        let last = animations.sample(&start, frames(&animations) as f32 / FPS);
+        assert_eq!(last, start);
+    }
+
+    #[test]
+    fn loop_is_back_where_it_started_at_the_duration() {
+        let start = start();
+        let animations = animations(AnimationMode::Loop);
+        assert_eq!(animations.duration(), Some(2f32));
+
+        let last = animations.sample(&start, frames(&animations) as f32 / FPS);
+        assert_eq!(last, start);
+    }
+
+    #[test]
+    fn the_duration_brings_every_animation_back_around() {
+        let mut start = start();
+        start.insert(
+            "b",
+            Parameter {
+                value: 0.5,
+                min: 0f32,
+                max: 1f32,
+                step: 0f32,
+            },
+        );
+        let mut animations = animations(AnimationMode::Loop);
+        animations.animate(ParameterAnimation::new("b", AnimationMode::PingPong));
+        assert_eq!(animations.duration(), Some(4f32));
+
+        let last = animations.sample(&start, frames(&animations) as f32 / FPS);
+        assert_eq!(last, start);
+    }
+}
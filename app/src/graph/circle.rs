@@ -0,0 +1,65 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use two_dimensional::{Rect, View};
+
+use super::{Plottable, SceneBuilder};
+use crate::document::{PlotDocument, ShapeKind};
+
+//about this many pixels per segment, so small circles aren't wasteful and big ones stay round
+const SEGMENT_LENGTH: f32 = 5f32;
+const MIN_SEGMENTS: usize = 16;
+const MAX_SEGMENTS: usize = 2048;
+
+//the points where (x - cx)^2 + (y - cy)^2 = r^2
+//driven by one point it follows the center, a second point sets the radius to pass through it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Circle {
+    pub center: (f32, f32),
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: (f32, f32), radius: f32) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Plottable for Circle {
+    fn generate(&self, view: &View, out: &mut SceneBuilder) {
+        //the circle is an ellipse on screen when x and y are scaled differently
+        let circumference = PI * self.radius * (view.scale.0 + view.scale.1);
+        let segments = ((circumference / SEGMENT_LENGTH) as usize).clamp(MIN_SEGMENTS, MAX_SEGMENTS);
+        let (cx, cy) = self.center;
+        let points: Vec<(f32, f32)> = (0..=segments)
+            .map(|i| {
+                let angle = 2f32 * PI * i as f32 / segments as f32;
+                (cx + self.radius * angle.cos(), cy + self.radius * angle.sin())
+            })
+            .collect();
+
+        out.fill_polygon(&points);
+        out.polyline(points);
+    }
+
+    fn bounds(&self, _domain: (f32, f32)) -> Option<Rect> {
+        let (cx, cy) = self.center;
+        Some(Rect::from_corners(
+            (cx - self.radius, cy - self.radius),
+            (cx + self.radius, cy + self.radius),
+        ))
+    }
+
+    fn drive(&mut self, points: &[(f32, f32)]) {
+        if let Some(&center) = points.first() {
+            self.center = center;
+        }
+        if let Some(&(x, y)) = points.get(1) {
+            self.radius = ((x - self.center.0).powi(2) + (y - self.center.1).powi(2)).sqrt();
+        }
+    }
+
+    fn to_document(&self) -> Option<PlotDocument> {
+        Some(PlotDocument::Shape(ShapeKind::Circle(self.clone())))
+    }
+}
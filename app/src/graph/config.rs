@@ -0,0 +1,30 @@
+use std::{fmt, fs, path::Path};
+
+use serde::de::DeserializeOwned;
+
+//what went wrong loading a settings file like a theme, named by what the file holds
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(&'static str, std::io::Error),
+    Parse(&'static str, ron::error::SpannedError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(kind, e) => write!(f, "couldn't read {} file: {}", kind, e),
+            Self::Parse(kind, e) => write!(f, "couldn't parse {} file: {}", kind, e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+//settings files are written in the same ron format as documents
+pub(crate) fn load_config<T: DeserializeOwned>(
+    kind: &'static str,
+    path: impl AsRef<Path>,
+) -> Result<T, ConfigError> {
+    let source = fs::read_to_string(path).map_err(|e| ConfigError::Io(kind, e))?;
+    ron::from_str(&source).map_err(|e| ConfigError::Parse(kind, e))
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use bevy_ecs::prelude::*;
+use two_dimensional::{Rect, View};
+
+use super::{Draggable, Dragged, Plot, PlotBundle, Plottable, SceneBuilder, Stroke, ZOrder};
+use crate::document::PlotDocument;
+
+//control points are drawn over the plots they drive
+const CONTROL_POINT_Z: i32 = 100;
+//a gray that shows on light and dark backgrounds, control points don't take colors from the palette
+const CONTROL_POINT_STROKE: Stroke = Stroke {
+    color: [0.5, 0.5, 0.5],
+    width: 5f32,
+};
+
+//a point the user can drag around, the plots driven by it follow it in the same frame
+pub struct ControlPoint {
+    pub position: (f32, f32),
+}
+
+impl ControlPoint {
+    pub fn new(position: (f32, f32)) -> Self {
+        Self { position }
+    }
+}
+
+impl Plottable for ControlPoint {
+    fn generate(&self, _view: &View, out: &mut SceneBuilder) {
+        out.point(self.position);
+    }
+
+    fn bounds(&self, _domain: (f32, f32)) -> Option<Rect> {
+        Some(Rect::from_corners(self.position, self.position))
+    }
+
+    fn to_document(&self) -> Option<PlotDocument> {
+        Some(PlotDocument::Point(self.position))
+    }
+}
+
+//a control point ready to be spawned, on top of the other plots and draggable
+#[derive(Bundle)]
+pub struct ControlPointBundle {
+    #[bundle]
+    pub plot: PlotBundle,
+    pub draggable: Draggable,
+}
+
+impl ControlPointBundle {
+    pub fn new(position: (f32, f32)) -> Self {
+        Self {
+            plot: PlotBundle::new(ControlPoint::new(position))
+                .with_stroke(CONTROL_POINT_STROKE)
+                .with_z_order(ZOrder(CONTROL_POINT_Z)),
+            draggable: Draggable,
+        }
+    }
+}
+
+//the control points a plot takes its inputs from, see Plottable::drive
+#[derive(Component)]
+pub struct DrivenBy(pub Vec<Entity>);
+
+//move the dragged control points along with the cursor, keeping where on the point it was grabbed
+pub fn drag_control_points(mut events: EventReader<Dragged>, mut plots: Query<&mut Plot>) {
+    for event in events.iter() {
+        let is_control_point = plots
+            .get(event.entity)
+            .is_ok_and(|plot| plot.downcast_ref::<ControlPoint>().is_some());
+        if !is_control_point {
+            continue;
+        }
+
+        if let Ok(mut plot) = plots.get_mut(event.entity) {
+            if let Some(point) = plot.downcast_mut::<ControlPoint>() {
+                point.position.0 += event.delta.0;
+                point.position.1 += event.delta.1;
+            }
+        }
+    }
+}
+
+type Sources = (Entity, &'static Plot, ChangeTrackers<Plot>);
+type Driven = (&'static DrivenBy, ChangeTrackers<DrivenBy>, &'static mut Plot);
+
+//hand the positions of moved control points to every plot driven by them
+//the driven plots count as changed, so they regenerate this frame
+pub fn drive_dependents(
+    mut plots: ParamSet<(Query<Sources>, Query<Driven>)>,
+) {
+    let points: HashMap<Entity, ((f32, f32), bool)> = plots
+        .p0()
+        .iter()
+        .filter_map(|(entity, plot, trackers)| {
+            let point = plot.downcast_ref::<ControlPoint>()?;
+            Some((entity, (point.position, trackers.is_changed())))
+        })
+        .collect();
+
+    for (driven_by, trackers, mut plot) in &mut plots.p1() {
+        let moved = driven_by
+            .0
+            .iter()
+            .any(|source| points.get(source).is_some_and(|(_, changed)| *changed));
+        if !moved && !trackers.is_changed() {
+            continue;
+        }
+
+        //a plot driven for the first time picks up where its points already are
+        //sources that were despawned or aren't control points are left out
+        let positions: Vec<(f32, f32)> = driven_by
+            .0
+            .iter()
+            .filter_map(|source| points.get(source).map(|(position, _)| *position))
+            .collect();
+        plot.drive(&positions);
+    }
+}
@@ -0,0 +1,66 @@
+use bevy_ecs::prelude::*;
+use two_dimensional::View;
+
+use super::{add_label, add_screen_rect, Cursor, Overlay, OverlayLayer, Theme};
+
+//the crosshair's lines, in logical pixels
+const CROSSHAIR_WIDTH: f32 = 1f32;
+const CROSSHAIR_ALPHA: f32 = 0.5;
+//past this many decimals an f32 can't tell the digits apart anyway
+const MAX_DECIMALS: usize = 7;
+
+//lines through the cursor and a label with the graph coordinates under it, off until toggled
+#[derive(Default)]
+pub struct Crosshair {
+    pub visible: bool,
+}
+
+//enough decimals that moving the cursor by one pixel changes the last one
+pub fn decimals(units_per_pixel: f32) -> usize {
+    let digits = -units_per_pixel.log10().floor();
+    (digits.max(0f32) as usize).min(MAX_DECIMALS)
+}
+
+//a graph space point formatted to the precision the view can show
+pub fn format_point(view: &View, (x, y): (f32, f32)) -> String {
+    format!(
+        "({:.*}, {:.*})",
+        decimals(view.aspect.0),
+        x,
+        decimals(view.aspect.1),
+        y
+    )
+}
+
+pub fn update_crosshair(
+    crosshair: Res<Crosshair>,
+    cursor: Res<Cursor>,
+    view: Res<View>,
+    theme: Res<Theme>,
+    mut overlay: ResMut<Overlay>,
+) {
+    //panning under a still cursor changes the coordinates too
+    if !crosshair.is_changed() && !cursor.is_changed() && !view.is_changed() && !theme.is_changed() {
+        return;
+    }
+
+    let mut layer = OverlayLayer::default();
+    if let (true, Some(position)) = (crosshair.visible, cursor.position) {
+        let (x, y) = (position.x as f32, position.y as f32);
+        let (width, height) = view.screen_size();
+        let half_width = CROSSHAIR_WIDTH * view.scale_factor as f32 / 2f32;
+        let [r, g, b] = theme.axis;
+        let color = [r, g, b, CROSSHAIR_ALPHA];
+        add_screen_rect(&mut layer.lines, (x - half_width, 0f32), (x + half_width, height), color);
+        add_screen_rect(&mut layer.lines, (0f32, y - half_width), (width, y + half_width), color);
+
+        let label = format_point(&view, view.screen_to_graph(position));
+        add_label(&mut layer, &label, (x, y), &view, &theme);
+    }
+
+    //leave the overlay alone while there is nothing to show, so it isn't re-uploaded
+    if layer.is_empty() && overlay.crosshair.is_empty() {
+        return;
+    }
+    overlay.crosshair = layer;
+}
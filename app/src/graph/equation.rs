@@ -1,47 +1,137 @@
-use bevy_ecs::prelude::*;
-use two_dimensional::{
-    primitives::line::{Line, LineList},
-    View,
-};
+use two_dimensional::{Rect, View};
 
-pub trait Equation {
+use super::{AsAny, Formula, Plottable, SceneBuilder};
+use crate::document::{EquationDocument, PlotDocument};
+
+pub trait Equation: AsAny {
     fn f(&self, x: f32) -> f32;
-}
 
+    //f at every x, the samplers go through this so equations that can evaluate many points
+    //at once, like compiled formulas, don't pay for one call per point
+    fn f_many(&self, xs: &[f32], ys: &mut [f32]) {
+        for (x, y) in xs.iter().zip(ys) {
+            *y = self.f(*x);
+        }
+    }
+
+    //the slope at x, found numerically with a central difference unless an equation knows better
+    fn derivative(&self, x: f32) -> f32 {
+        let h = 1e-3 * x.abs().max(1f32);
+        (self.f(x + h) - self.f(x - h)) / (2f32 * h)
+    }
+
+    //the form this equation is saved in, equations that can't be saved return None
+    fn to_document(&self) -> Option<EquationDocument> {
+        None
+    }
+}
 
-#[derive(Component)]
 pub struct EquationBox {
     equation: Box<dyn Equation + Send + Sync>
 }
 
-//implement a system that generates lines for all equations
-pub fn generate_equation_lines(query: Query<&EquationBox>, view: Res<View>, lines: ResMut<LineList>) {
-    for eq_box in &query {
-        let equation = eq_box.equation;
+impl EquationBox {
+    pub fn new(equation: impl Equation + Send + Sync + 'static) -> Self {
+        Self {
+            equation: Box::new(equation),
+        }
+    }
 
+    pub fn f(&self, x: f32) -> f32 {
+        self.equation.f(x)
+    }
+
+    pub fn f_many(&self, xs: &[f32], ys: &mut [f32]) {
+        self.equation.f_many(xs, ys);
+    }
+
+    pub fn derivative(&self, x: f32) -> f32 {
+        self.equation.derivative(x)
+    }
+
+    pub fn to_document(&self) -> Option<EquationDocument> {
+        self.equation.to_document()
+    }
+
+    //the formula this equation is, if it is one, only formulas have parameters and call user defined functions
+    pub fn formula(&self) -> Option<&Formula> {
+        self.equation.as_ref().as_any().downcast_ref()
+    }
+
+    pub fn formula_mut(&mut self) -> Option<&mut Formula> {
+        self.equation.as_mut().as_any_mut().downcast_mut()
+    }
+}
+
+//how many points an equation is sampled at to find its bounds
+const BOUNDS_SAMPLES: usize = 1000;
+
+//an equation generates lines across the whole width of the view
+impl Plottable for EquationBox {
+    fn generate(&self, view: &View, out: &mut SceneBuilder) {
         //in this case, we want 5 pixels per increment (5 pixel long lines)
         let line_width = 5f32;
-        //TODO: do this in a rust idiomatic way
-        //calculate what our x-step should be based on a 5 pixel increment
-        let x_step = line_width / view.scale;
-        let mut x = view.left;
-        let mut y = equation.f(x);
-        while x < view.right {
-            let next_x = x + x_step;
-            let next_y = equation.f(next_x);
+        //calculate what our x-step should be based on a 5 pixel increment,
+        //the last sample lands on or just past the right edge
+        let x_step = line_width / view.scale.0;
+        let steps = ((view.right - view.left) / x_step).ceil().max(1f32) as usize;
+        let xs: Vec<f32> = (0..=steps).map(|i| view.left + i as f32 * x_step).collect();
+        let mut ys = vec![0f32; xs.len()];
+        self.equation.f_many(&xs, &mut ys);
+
+        for i in 0..steps {
+            let (x, next_x) = (xs[i], xs[i + 1]);
+            let (y, next_y) = (ys[i], ys[i + 1]);
+            //nothing is drawn where the equation is undefined
+            if !y.is_finite() || !next_y.is_finite() {
+                continue;
+            }
+            //filled equations shade the area between the curve and the x axis
+            //a segment crossing the axis is split where it crosses, one quad would twist there
+            if out.fill().is_some() {
+                if y * next_y < 0f32 {
+                    let root = x + (next_x - x) * y / (y - next_y);
+                    out.fill_polygon(&[(x, 0f32), (x, y), (root, 0f32)]);
+                    out.fill_polygon(&[(root, 0f32), (next_x, next_y), (next_x, 0f32)]);
+                } else {
+                    out.fill_polygon(&[(x, 0f32), (x, y), (next_x, next_y), (next_x, 0f32)]);
+                }
+            }
             //draw a line from the two points
-            lines.add_line(
-                &Line {
-                    width: 4f32,
-                    start: (x, y),
-                    end: (next_x, next_y),
-                    color: [1f32, 0f32, 0f32],
-                },
-                view.as_ref(),
-            );
-
-            x = next_x;
-            y = next_y;
+            out.line((x, y), (next_x, next_y));
         }
     }
-}
\ No newline at end of file
+
+    //the samples that are undefined, like poles or logs of negatives, are left out
+    fn bounds(&self, (start, end): (f32, f32)) -> Option<Rect> {
+        let step = (end - start) / (BOUNDS_SAMPLES - 1) as f32;
+        let xs: Vec<f32> = (0..BOUNDS_SAMPLES).map(|i| start + i as f32 * step).collect();
+        let mut ys = vec![0f32; BOUNDS_SAMPLES];
+        self.equation.f_many(&xs, &mut ys);
+        let (bottom, top) = ys
+            .into_iter()
+            .filter(|y| y.is_finite())
+            .fold(None, |range: Option<(f32, f32)>, y| match range {
+                Some((bottom, top)) => Some((bottom.min(y), top.max(y))),
+                None => Some((y, y)),
+            })?;
+        Some(Rect {
+            left: start,
+            right: end,
+            bottom,
+            top,
+        })
+    }
+
+    fn equation(&self) -> Option<&EquationBox> {
+        Some(self)
+    }
+
+    fn equation_mut(&mut self) -> Option<&mut EquationBox> {
+        Some(self)
+    }
+
+    fn to_document(&self) -> Option<PlotDocument> {
+        self.equation.to_document().map(PlotDocument::Equation)
+    }
+}
@@ -0,0 +1,151 @@
+use bevy_ecs::prelude::*;
+use two_dimensional::{CameraController, Rect};
+
+use super::{Geometry, Plot, Visibility};
+
+//how the view is fit to what is plotted
+pub struct FitSettings {
+    //how much room to leave around the content, as a fraction of its size
+    pub padding: f32,
+    //the range of x that plots going on forever, like equations, are fit over
+    pub domain: (f32, f32),
+}
+
+impl Default for FitSettings {
+    fn default() -> Self {
+        Self {
+            padding: 0.05,
+            domain: (-10f32, 10f32),
+        }
+    }
+}
+
+//send this to fly the camera to everything that is currently visible,
+//nothing happens if there is nothing to fit to
+pub struct FitToContent;
+
+//the rectangle holding every visible plot, plots that can't tell their own bounds fall back to
+//the lines they last generated, which only cover what was on screen
+fn content_bounds(
+    plots: &Query<(&Plot, Option<&Geometry>, &Visibility)>,
+    domain: (f32, f32),
+) -> Option<Rect> {
+    plots
+        .iter()
+        .filter(|(_, _, visibility)| visibility.visible)
+        .filter_map(|(plot, geometry, _)| {
+            plot.bounds(domain)
+                .or_else(|| geometry.and_then(|geometry| geometry.lines.bounds()))
+        })
+        .reduce(|bounds, other| bounds.union(&other))
+}
+
+pub fn fit_to_content(
+    mut events: EventReader<FitToContent>,
+    settings: Res<FitSettings>,
+    plots: Query<(&Plot, Option<&Geometry>, &Visibility)>,
+    mut cam_controller: ResMut<CameraController>,
+) {
+    //several requests in one frame only need one fit
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    if let Some(bounds) = content_bounds(&plots, settings.domain) {
+        cam_controller.fit(bounds.padded(settings.padding));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use two_dimensional::View;
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::expression::Expression;
+    use crate::graph::{ControlPoint, EquationBox, Formula, PlotBundle};
+
+    //what content_bounds found the last time the stage ran
+    struct Bounds(Option<Rect>);
+
+    fn record_bounds(
+        plots: Query<(&Plot, Option<&Geometry>, &Visibility)>,
+        settings: Res<FitSettings>,
+        mut bounds: ResMut<Bounds>,
+    ) {
+        bounds.0 = content_bounds(&plots, settings.domain);
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(FitSettings::default());
+        world.insert_resource(Events::<FitToContent>::default());
+        world.insert_resource(CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600)));
+        world.insert_resource(Bounds(None));
+        world
+    }
+
+    fn formula(source: &str) -> PlotBundle {
+        PlotBundle::new(EquationBox::new(Formula::new(Expression::parse(source).unwrap())))
+    }
+
+    //fits once and returns the bounds it fit to
+    fn fit(world: &mut World) -> Option<Rect> {
+        world.resource_mut::<Events<FitToContent>>().send(FitToContent);
+        SystemStage::single_threaded()
+            .with_system(record_bounds)
+            .with_system(fit_to_content)
+            .run(world);
+        world.resource::<Bounds>().0
+    }
+
+    fn settle(world: &mut World) -> View {
+        let mut camera = world.resource_mut::<CameraController>();
+        for _ in 0..600 {
+            camera.tick(1f32 / 60f32);
+        }
+        assert!(!camera.is_animating());
+        camera.view()
+    }
+
+    #[test]
+    fn empty_scenes_leave_the_camera_alone() {
+        let mut world = world();
+        let before = world.resource::<CameraController>().view();
+        world.spawn().insert_bundle(formula("x").with_visibility(Visibility { visible: false }));
+
+        assert_eq!(fit(&mut world), None);
+        assert!(!world.resource::<CameraController>().is_animating());
+        assert!(settle(&mut world) == before);
+    }
+
+    #[test]
+    fn a_single_point_is_centered_at_the_same_zoom() {
+        let mut world = world();
+        let scale = world.resource::<CameraController>().scale();
+        world.spawn().insert_bundle(PlotBundle::new(ControlPoint::new((1f32, 2f32))));
+
+        assert_eq!(fit(&mut world), Some(Rect::from_corners((1f32, 2f32), (1f32, 2f32))));
+        let view = settle(&mut world);
+        assert!((view.center_x - 1f32).abs() < 1e-4 && (view.center_y - 2f32).abs() < 1e-4);
+        assert!((view.scale.0 - scale.0).abs() < 1e-3 && (view.scale.1 - scale.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn non_finite_samples_are_left_out() {
+        let mut world = world();
+        //undefined left of the origin
+        world.spawn().insert_bundle(formula("sqrt(x)"));
+        //undefined everywhere, so it adds nothing
+        world.spawn().insert_bundle(formula("sqrt(-1-x^2)"));
+
+        let bounds = fit(&mut world).unwrap();
+        assert_eq!((bounds.left, bounds.right), FitSettings::default().domain);
+        assert!(bounds.bottom >= 0f32 && bounds.bottom < 0.2, "bottom at {}", bounds.bottom);
+        assert!((bounds.top - 10f32.sqrt()).abs() < 1e-4, "top at {}", bounds.top);
+
+        let view = settle(&mut world);
+        assert!(view.center_x.is_finite() && view.center_y.is_finite());
+        assert!(view.scale.0.is_finite() && view.scale.1.is_finite());
+    }
+}
@@ -0,0 +1,146 @@
+use bevy_ecs::prelude::*;
+
+use super::{Equation, EquationBox, Plot};
+use crate::{
+    document::EquationDocument,
+    expression::{Definitions, Expression, ParseError, Program},
+};
+
+//an equation typed in as an expression, its parameters start at zero until bind_parameters
+//gives them their values
+//formulas that call user defined functions have nothing to draw until link_formulas links them
+pub struct Formula {
+    //as it was typed
+    expression: Expression,
+    //with the definitions it calls inlined
+    linked: Option<Expression>,
+    //the linked expression compiled, what is actually evaluated
+    program: Option<Program>,
+    links: Option<Vec<(String, u64)>>,
+    values: Vec<f32>,
+}
+
+impl Formula {
+    pub fn new(expression: Expression) -> Self {
+        let mut formula = Self {
+            expression,
+            linked: None,
+            program: None,
+            links: None,
+            values: Vec::new(),
+        };
+        if formula.expression.functions().is_empty() {
+            formula.set_linked(Some(formula.expression.clone()), Vec::new());
+        }
+        formula
+    }
+
+    //plots a definition by name, following it whenever it is redefined
+    pub fn named(name: &str) -> Result<Self, ParseError> {
+        Ok(Self::new(Expression::parse(&format!("{}(x)", name))?))
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub fn linked(&self) -> Option<&Expression> {
+        self.linked.as_ref()
+    }
+
+    fn set_linked(&mut self, linked: Option<Expression>, links: Vec<(String, u64)>) {
+        let count = linked.as_ref().map_or(0, |linked| linked.parameters().len());
+        self.program = linked.as_ref().map(Expression::compile);
+        self.linked = linked;
+        self.links = Some(links);
+        //placeholders until bind_parameters, which runs before anything reads the curve's bounds,
+        //so a fit sees the saved parameter values rather than a flat line
+        self.values = vec![0f32; count];
+    }
+
+    //the free parameters the formula reads once linked, looked up by name in Parameters
+    pub fn parameters(&self) -> &[String] {
+        self.linked.as_ref().map_or(&[], |linked| linked.parameters())
+    }
+
+    //the values the parameters were last bound to, in the same order
+    pub fn bound(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn bind(&mut self, values: &[f32]) {
+        self.values = values.to_vec();
+        if let Some(program) = &mut self.program {
+            let values: Vec<f64> = values.iter().map(|&value| value as f64).collect();
+            program.bind(&values);
+        }
+    }
+
+    //the user defined functions the formula was linked against with their revisions, see Definitions
+    //None until it has been linked the first time
+    pub fn links(&self) -> Option<&[(String, u64)]> {
+        self.links.as_deref()
+    }
+
+    pub fn link(&mut self, definitions: &Definitions) {
+        let mut links = Vec::new();
+        let linked = match definitions.link(&self.expression, &mut links) {
+            Ok(linked) => Some(linked),
+            Err(e) => {
+                log::warn!("couldn't plot {}: {}", self.expression, e);
+                None
+            }
+        };
+        self.set_linked(linked, links);
+    }
+}
+
+impl Equation for Formula {
+    fn f(&self, x: f32) -> f32 {
+        match &self.program {
+            Some(program) => program.eval(x as f64) as f32,
+            None => f32::NAN,
+        }
+    }
+
+    fn f_many(&self, xs: &[f32], ys: &mut [f32]) {
+        let program = match &self.program {
+            Some(program) => program,
+            None => return ys.fill(f32::NAN),
+        };
+        //evaluated in double precision like f, then narrowed back
+        let wide: Vec<f64> = xs.iter().map(|&x| x as f64).collect();
+        let mut out = vec![0f64; wide.len()];
+        program.eval_many(&wide, &mut out);
+        for (y, out) in ys.iter_mut().zip(out) {
+            *y = out as f32;
+        }
+    }
+
+    fn to_document(&self) -> Option<EquationDocument> {
+        Some(EquationDocument::Expression(self.expression.source().to_string()))
+    }
+}
+
+//link the formulas that haven't been yet, and relink the ones that call a definition that changed
+//so redefining a function regenerates every curve that depends on it, directly or not, and nothing else
+pub fn link_formulas(definitions: Res<Definitions>, mut plots: Query<&mut Plot>) {
+    for mut plot in &mut plots {
+        if !definitions.is_changed() && !plot.is_changed() {
+            continue;
+        }
+        let stale = match plot.equation().and_then(EquationBox::formula) {
+            Some(formula) => match formula.links() {
+                Some(links) => definitions.is_stale(links),
+                None => true,
+            },
+            None => continue,
+        };
+        if !stale {
+            continue;
+        }
+        if let Some(formula) = plot.equation_mut().and_then(EquationBox::formula_mut) {
+            formula.link(&definitions);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy_ecs::prelude::*;
+use two_dimensional::primitives::line::LineList;
+
+use super::{Plot, Visibility, ZOrder};
+
+//the lines an entity generated for the current view, kept until the view or the entity changes
+#[derive(Component, Default)]
+pub struct Geometry {
+    pub lines: LineList,
+    pub shapes: Shapes,
+}
+
+//the graph space shapes the lines were built from, kept so the cursor can be tested against them
+#[derive(Clone, Default)]
+pub struct Shapes {
+    pub segments: Vec<((f32, f32), (f32, f32))>,
+    pub points: Vec<(f32, f32)>,
+    //only the filled ones
+    pub polygons: Vec<Vec<(f32, f32)>>,
+}
+
+//the grid isn't an entity, so its cached lines live in a resource
+#[derive(Default)]
+pub struct GridGeometry {
+    pub lines: LineList,
+}
+
+//every plotted entity caches its lines in a geometry component
+pub fn attach_geometry(
+    mut commands: Commands,
+    query: Query<Entity, (With<Plot>, Without<Geometry>)>,
+) {
+    for entity in &query {
+        commands.entity(entity).insert(Geometry::default());
+    }
+}
+
+type DrawOrderChanged = Or<(Changed<Geometry>, Changed<Visibility>, Changed<ZOrder>)>;
+
+//rebuild the line list handed to the gpu, but only if some cached geometry changed since last frame
+pub fn collect_lines(
+    grid: Res<GridGeometry>,
+    geometry: Query<(Entity, &Geometry, &Visibility, &ZOrder)>,
+    changed: Query<(), DrawOrderChanged>,
+    removed: RemovedComponents<Geometry>,
+    mut lines: ResMut<LineList>,
+) {
+    if !grid.is_changed() && changed.is_empty() && removed.iter().next().is_none() {
+        return;
+    }
+
+    //plots are drawn by z order, with the entity as a tie breaker so the order is stable
+    let mut visible: Vec<(ZOrder, Entity, &Geometry)> = geometry
+        .iter()
+        .filter(|(_, _, visibility, _)| visibility.visible)
+        .map(|(entity, geometry, _, z_order)| (*z_order, entity, geometry))
+        .collect();
+    visible.sort_by_key(|(z_order, entity, _)| (*z_order, *entity));
+
+    //the grid goes in first so curves are drawn over it
+    let mut new_lines = grid.lines.clone();
+    for (_, _, geometry) in visible {
+        new_lines.extend(&geometry.lines);
+    }
+    *lines = new_lines;
+}
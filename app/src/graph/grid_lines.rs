@@ -1,7 +1,49 @@
 use bevy_ecs::prelude::*;
-use two_dimensional::{primitives::line::{Line, LineList, LinePassData}, View};
+use serde::{Deserialize, Deserializer, Serialize};
+use two_dimensional::{primitives::line::{Line, LineList}, View};
+
+use super::{GridGeometry, Theme};
+
+//settings for the background grid, widths are in pixels and the colors come from the theme
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GridSettings {
+    pub visible: bool,
+    //roughly how many grid lines we want between the center and the top of the view
+    pub increments_to_top: f32,
+    //how many minor cells each major cell is split into, 0 or 1 turns the minor grid off
+    #[serde(deserialize_with = "clamp_minor_divisions")]
+    pub minor_divisions: u32,
+    pub line_width: f32,
+    pub minor_width: f32,
+    pub axis_width: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            increments_to_top: 5f32,
+            minor_divisions: 4,
+            line_width: 2f32,
+            minor_width: 1f32,
+            axis_width: 4f32,
+        }
+    }
+}
+
+//more minor lines than this per major cell can't be told apart on screen
+const MAX_MINOR_DIVISIONS: u32 = 16;
+
+//a pass that would draw more lines than this along one axis is skipped
+const MAX_LINES_PER_AXIS: usize = 2000;
+
+//documents are written by hand too, so a huge division count is cut down rather than drawn
+fn clamp_minor_divisions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    Ok(u32::deserialize(deserializer)?.min(MAX_MINOR_DIVISIONS))
+}
 
 struct VerticalLine;
+#[allow(clippy::new_ret_no_self)]
 impl VerticalLine {
     fn new(x: f32, bottom: f32, top: f32, width: f32, color: [f32; 3]) -> Line {
         Line {width, start: (x, bottom), end: (x, top), color}
@@ -9,36 +51,120 @@ impl VerticalLine {
 }
 
 struct HorizontalLine;
+#[allow(clippy::new_ret_no_self)]
 impl HorizontalLine {
     fn new(y: f32, left: f32, right: f32, width: f32, color: [f32; 3]) -> Line {
         Line {width, start: (left, y), end: (right, y), color}
     }
 }
 
-pub fn generate_grid_lines(In(line_pass_data): In<LinePassData>) -> LinePassData {
-    let view: &View = &line_pass_data.view;
-    let lines: &mut LineList = &mut line_pass_data.lines;
+//every vertical and horizontal line within the view, x and y have their own spacing
+fn add_grid(lines: &mut LineList, view: &View, (step_x, step_y): (f32, f32), width: f32, color: [f32; 3]) {
+    for x in grid_positions(view.left, view.right, step_x) {
+        lines.add_line(&VerticalLine::new(x, view.bottom, view.top, width, color), view);
+    }
+    for y in grid_positions(view.bottom, view.top, step_y) {
+        lines.add_line(&HorizontalLine::new(y, view.left, view.right, width, color), view);
+    }
+}
 
-    let incs_to_top = 5f32;
-    let step = 2f32.powf(f32::trunc(f32::log2((view.top - view.center_y) / incs_to_top)));
-    
-    //construct all the vertical lines within view, with an step
-    let mut x = f32::trunc(view.left / step) * step; //fancy line for finding the first x-pos
-    while x < view.right {
-        lines.add_line(&VerticalLine::new(x, view.bottom, view.top, 2f32, [0.3, 0.3, 0.3]), view);
-        x += step;
+//the multiples of step from start up to end, none if there would be too many to draw
+fn grid_positions(start: f32, end: f32, step: f32) -> Vec<f32> {
+    let count = (end - start) / step;
+    if !(count >= 0f32 && count < MAX_LINES_PER_AXIS as f32) {
+        return Vec::new();
     }
 
-    //construct all the horizontal lines within view, with a step
-    let mut y = f32::trunc(view.bottom / step) * step;
-    while y < view.top {
-        lines.add_line(&HorizontalLine::new(y, view.left, view.right, 2f32, [0.3, 0.3, 0.3]), view);
-        y += step;
+    let mut positions = Vec::new();
+    let mut x = f32::trunc(start / step) * step; //fancy line for finding the first position
+    while x < end && positions.len() < MAX_LINES_PER_AXIS {
+        positions.push(x);
+        //far enough from the origin a small step is lost to rounding and x stops moving
+        let next = x + step;
+        if next <= x {
+            break;
+        }
+        x = next;
+    }
+    positions
+}
+
+//the power of two closest below a spacing in graph units
+fn round_step(step: f32) -> f32 {
+    2f32.powf(f32::floor(f32::log2(step)))
+}
+
+//only regenerated when the view, the grid settings or the theme change
+pub fn generate_grid_lines(
+    view: Res<View>,
+    grid: Res<GridSettings>,
+    theme: Res<Theme>,
+    mut geometry: ResMut<GridGeometry>,
+) {
+    if !view.is_changed() && !grid.is_changed() && !theme.is_changed() {
+        return;
     }
 
+    let mut lines = LineList::new();
+    if !grid.visible {
+        geometry.lines = lines;
+        return;
+    }
+
+    let view: &View = &view;
+
+    //the spacing is picked in pixels from the height of the view, then each axis turns it into graph units
+    let pixel_step = (view.top - view.center_y) * view.scale.1 / grid.increments_to_top;
+    let step = (round_step(pixel_step / view.scale.0), round_step(pixel_step / view.scale.1));
+    
+    //minor lines go in first so the major lines are drawn over them
+    if grid.minor_divisions > 1 {
+        let divisions = grid.minor_divisions as f32;
+        let minor_step = (step.0 / divisions, step.1 / divisions);
+        add_grid(&mut lines, view, minor_step, grid.minor_width, theme.minor_grid);
+    }
+    add_grid(&mut lines, view, step, grid.line_width, theme.major_grid);
+
     //construct our axis in a different color
-    lines.add_line(&VerticalLine::new(0f32, view.bottom, view.top, 4f32, [0f32, 0f32, 0f32]), view);
-    lines.add_line(&HorizontalLine::new(0f32, view.left, view.right, 4f32, [0f32, 0f32, 0f32]), view);
+    lines.add_line(&VerticalLine::new(0f32, view.bottom, view.top, grid.axis_width, theme.axis), view);
+    lines.add_line(&HorizontalLine::new(0f32, view.left, view.right, grid.axis_width, theme.axis), view);
+
+    geometry.lines = lines;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    line_pass_data
-}
\ No newline at end of file
+    #[test]
+    fn steps_round_down_to_a_power_of_two() {
+        assert_eq!(round_step(3f32), 2f32);
+        assert_eq!(round_step(1f32), 1f32);
+        assert_eq!(round_step(0.3), 0.25);
+        assert_eq!(round_step(0.01), 0.0078125);
+    }
+
+    #[test]
+    fn grid_positions_cover_the_range() {
+        assert_eq!(grid_positions(-1.5, 1.5, 1f32), vec![-1f32, 0f32, 1f32]);
+        assert_eq!(grid_positions(0f32, 1f32, 0.25), vec![0f32, 0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn grid_positions_stop_when_the_step_is_lost() {
+        //1e-3 is below the spacing of floats around 1e6, adding it never moves x
+        assert_eq!(grid_positions(1e6, 1e6 + 0.5, 1e-3).len(), 1);
+
+        assert!(grid_positions(0f32, 1f32, 0f32).is_empty());
+        assert!(grid_positions(0f32, 1e6, 1e-3).is_empty());
+        assert!(grid_positions(0f32, 1f32, f32::NAN).is_empty());
+    }
+
+    #[test]
+    fn loaded_minor_divisions_are_clamped() {
+        let source = "(visible: true, increments_to_top: 5, minor_divisions: 4000000000, \
+            line_width: 2, minor_width: 1, axis_width: 4)";
+        let grid: GridSettings = ron::from_str(source).unwrap();
+        assert_eq!(grid.minor_divisions, MAX_MINOR_DIVISIONS);
+    }
+}
@@ -0,0 +1,52 @@
+use bevy_ecs::prelude::*;
+use rendering::RenderContext;
+use two_dimensional::CameraController;
+use winit::{dpi::PhysicalPosition, event::WindowEvent};
+
+use super::PointerRouter;
+
+//window events are forwarded into the world by the event loop and read by the input stage
+pub type WindowEvents = Events<WindowEvent<'static>>;
+
+//where the mouse is, in physical pixels from the top left of the window, None when it is outside
+#[derive(Default)]
+pub struct Cursor {
+    pub position: Option<PhysicalPosition<f64>>,
+}
+
+pub fn track_cursor(mut events: EventReader<WindowEvent<'static>>, mut cursor: ResMut<Cursor>) {
+    for event in events.iter() {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => cursor.position = Some(*position),
+            WindowEvent::CursorLeft { .. } => cursor.position = None,
+            _ => {}
+        }
+    }
+}
+
+//pass events to our cam controller, after the entities under the cursor had their pick of them
+pub fn camera_input(
+    mut events: EventReader<WindowEvent<'static>>,
+    mut router: PointerRouter,
+    mut cam_controller: ResMut<CameraController>,
+) {
+    for event in events.iter() {
+        if !router.route(event) {
+            cam_controller.event(event);
+        }
+    }
+}
+
+//resize the surface and let the camera know about its new resolution
+pub fn resize_surface(
+    mut events: EventReader<WindowEvent<'static>>,
+    mut render_context: ResMut<RenderContext>,
+    mut cam_controller: ResMut<CameraController>,
+) {
+    for event in events.iter() {
+        if let WindowEvent::Resized(size) = event {
+            render_context.resize(*size);
+            cam_controller.resize(*size);
+        }
+    }
+}
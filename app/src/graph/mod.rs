@@ -2,7 +2,63 @@ mod renderer;
 mod grid_lines;
 mod equation;
 mod normal;
+mod style;
+mod input;
+mod geometry;
+mod plot;
+mod config;
+mod theme;
+mod navigation;
+mod overlay;
+mod time;
+mod fit;
+mod crosshair;
+mod picking;
+mod trace;
+mod pointer;
+mod control;
+mod circle;
+mod tangent;
+mod formula;
+mod parameters;
+mod slider;
+mod animation;
 
-pub use renderer::GraphRenderContext;
+pub use renderer::{
+    begin_frame, clear_background, init_graph_render_context, present_frame, update_camera_uniform, update_view,
+    GraphRenderContext,
+};
+pub use grid_lines::{generate_grid_lines, GridSettings};
+pub use equation::{Equation, EquationBox};
+pub use formula::{link_formulas, Formula};
+pub use circle::Circle;
+pub use tangent::TangentLine;
+pub use plot::{generate_plots, AsAny, Plot, PlotBundle, Plottable, SceneBuilder};
+pub use input::{camera_input, resize_surface, track_cursor, Cursor, WindowEvents};
+pub use geometry::{attach_geometry, collect_lines, Geometry, GridGeometry, Shapes};
+pub use style::{assign_palette_colors, AutoColor, Fill, Palette, Stroke, Visibility, ZOrder};
+
+pub use config::ConfigError;
+pub use theme::{apply_theme, Theme};
+pub use time::{animate_camera, update_frame_time, FrameTime, Redraw};
+pub use overlay::{
+    add_label, add_screen_rect, render_overlay, update_selection_overlay, Overlay, OverlayLayer,
+};
+pub use picking::{hover_pick, pick, Hover, Hovered, Pick, PickSettings};
+pub use pointer::{
+    route_hover, Clicked, DragEnded, DragStarted, Draggable, Dragged, HoverEnter, HoverExit, Pointer,
+    PointerRouter, Widget, WidgetInput, WidgetPhase, Widgets,
+};
+pub use parameters::{bind_parameters, register_parameters, Parameter, Parameters};
+pub use slider::{slider_input, update_sliders};
+pub use animation::{
+    animate_parameters, animation_input, update_timeline_overlay, AnimationMode, Animations,
+    ParameterAnimation,
+};
+pub use control::{drag_control_points, drive_dependents, ControlPoint, ControlPointBundle, DrivenBy};
+pub use trace::{follow_trace, trace_input, update_trace_overlay, Trace};
+pub use crosshair::{decimals, format_point, update_crosshair, Crosshair};
+pub use fit::{fit_to_content, FitSettings, FitToContent};
+pub use navigation::{apply_zoom_sensitivity, keyboard_navigation, KeyMap, NavigationAction};
 
 pub use normal::Normal;
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::{collections::HashMap, path::Path};
+
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+use two_dimensional::{CameraController, ZoomSensitivity};
+use winit::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent};
+
+use super::config::{load_config, ConfigError};
+use super::{Crosshair, FitToContent, Trace};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NavigationAction {
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    //back to the origin at the default zoom
+    Reset,
+    //frame everything that is currently plotted
+    ZoomToFit,
+    //switch between one scale for both axes and a scale per axis
+    ToggleAspectLock,
+    //step back to the view before the last reset, fit or box zoom
+    UndoZoom,
+    //show or hide the lines and coordinates under the cursor
+    ToggleCrosshair,
+    //walk a marker along an equation, see Trace
+    ToggleTrace,
+    //show the traced equation's slope too
+    ToggleDerivative,
+    //run or stop the parameter animations, see Animations
+    PlayPause,
+    SpeedUp,
+    SlowDown,
+    //switch every animation to the next of linear, ping pong and loop
+    CycleAnimationMode,
+}
+
+//which keys move the camera and how far each press moves it, and how far wheels and pinches zoom
+//holding a key repeats it, so the steps are per key repeat
+//a key map file only needs the fields it changes, the rest keep their defaults,
+//a misspelled field is refused rather than quietly left at its default
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyMap {
+    //in pixels
+    pub pan_step: f32,
+    //in powers of two, 1 doubles or halves the zoom
+    pub zoom_step: f32,
+    //also in powers of two, per line of a notched wheel, per pixel of a touchpad scroll
+    //and per doubling of the distance between two fingers on a touch screen,
+    //trackpad pinches aren't reported by winit 0.27 so they can't zoom
+    pub wheel_line_zoom: f32,
+    pub wheel_pixel_zoom: f32,
+    pub pinch_zoom: f32,
+    //how far shift and the pan keys move the trace marker, in graph units
+    pub trace_step: f32,
+    pub bindings: HashMap<VirtualKeyCode, NavigationAction>,
+}
+
+impl KeyMap {
+    //key maps are saved in the same ron format as documents
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        load_config("key map", path)
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<NavigationAction> {
+        self.bindings.get(&key).copied()
+    }
+}
+
+impl Default for KeyMap {
+    //arrows and wasd pan, +/- zoom, 0 or home resets, f fits, l locks the aspect
+    //backspace or u undoes the last zoom, c toggles the crosshair, t starts and stops tracing
+    //and ' shows the slope while tracing, space plays the animations, [ and ] change their speed
+    //and m their mode
+    //the mouse wheel and two finger scrolls zoom toward the cursor, as do pinches on a touch screen
+    //but not on a trackpad, winit 0.27 doesn't report those
+    fn default() -> Self {
+        use NavigationAction::*;
+        use VirtualKeyCode as Key;
+
+        let bindings = [
+            (Key::Left, PanLeft),
+            (Key::A, PanLeft),
+            (Key::Right, PanRight),
+            (Key::D, PanRight),
+            (Key::Up, PanUp),
+            (Key::W, PanUp),
+            (Key::Down, PanDown),
+            (Key::S, PanDown),
+            (Key::Equals, ZoomIn),
+            (Key::Plus, ZoomIn),
+            (Key::NumpadAdd, ZoomIn),
+            (Key::Minus, ZoomOut),
+            (Key::NumpadSubtract, ZoomOut),
+            (Key::Key0, Reset),
+            (Key::Numpad0, Reset),
+            (Key::Home, Reset),
+            (Key::F, ZoomToFit),
+            (Key::L, ToggleAspectLock),
+            (Key::Back, UndoZoom),
+            (Key::U, UndoZoom),
+            (Key::C, ToggleCrosshair),
+            (Key::T, ToggleTrace),
+            (Key::Apostrophe, ToggleDerivative),
+            (Key::Space, PlayPause),
+            (Key::RBracket, SpeedUp),
+            (Key::LBracket, SlowDown),
+            (Key::M, CycleAnimationMode),
+        ];
+
+        let zoom_sensitivity = ZoomSensitivity::default();
+        Self {
+            pan_step: 50f32,
+            zoom_step: 0.25,
+            wheel_line_zoom: zoom_sensitivity.line,
+            wheel_pixel_zoom: zoom_sensitivity.pixel,
+            pinch_zoom: zoom_sensitivity.pinch,
+            trace_step: 0.1,
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+//hand the zoom factors to the camera whenever the key map is replaced
+pub fn apply_zoom_sensitivity(key_map: Res<KeyMap>, mut cam_controller: ResMut<CameraController>) {
+    if key_map.is_changed() {
+        cam_controller.set_zoom_sensitivity(ZoomSensitivity {
+            pixel: key_map.wheel_pixel_zoom,
+            line: key_map.wheel_line_zoom,
+            pinch: key_map.pinch_zoom,
+        });
+    }
+}
+
+//move the camera with the keyboard, shortcuts held with ctrl, alt or logo are left to the app
+//and the pan keys are left to the trace while it is active
+pub fn keyboard_navigation(
+    mut events: EventReader<WindowEvent<'static>>,
+    mut modifiers: Local<ModifiersState>,
+    key_map: Res<KeyMap>,
+    mut fit: EventWriter<FitToContent>,
+    mut crosshair: ResMut<Crosshair>,
+    trace: Res<Trace>,
+    mut cam_controller: ResMut<CameraController>,
+) {
+    for event in events.iter() {
+        let key = match event {
+            WindowEvent::ModifiersChanged(new_modifiers) => {
+                *modifiers = *new_modifiers;
+                continue;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => *key,
+            _ => continue,
+        };
+        if modifiers.ctrl() || modifiers.alt() || modifiers.logo() {
+            continue;
+        }
+
+        let step = key_map.pan_step;
+        let action = key_map.action(key);
+        let pan = matches!(
+            action,
+            Some(NavigationAction::PanLeft | NavigationAction::PanRight | NavigationAction::PanUp | NavigationAction::PanDown)
+        );
+        if pan && trace.is_active() {
+            continue;
+        }
+        match action {
+            Some(NavigationAction::PanLeft) => cam_controller.pan(-step, 0f32),
+            Some(NavigationAction::PanRight) => cam_controller.pan(step, 0f32),
+            Some(NavigationAction::PanUp) => cam_controller.pan(0f32, step),
+            Some(NavigationAction::PanDown) => cam_controller.pan(0f32, -step),
+            Some(NavigationAction::ZoomIn) => cam_controller.zoom_center(-key_map.zoom_step),
+            Some(NavigationAction::ZoomOut) => cam_controller.zoom_center(key_map.zoom_step),
+            Some(NavigationAction::Reset) => cam_controller.reset(),
+            Some(NavigationAction::ZoomToFit) => fit.send(FitToContent),
+            Some(NavigationAction::ToggleAspectLock) => {
+                let lock_aspect = !cam_controller.lock_aspect();
+                cam_controller.set_lock_aspect(lock_aspect);
+            }
+            Some(NavigationAction::UndoZoom) => {
+                cam_controller.undo_zoom();
+            }
+            Some(NavigationAction::ToggleCrosshair) => crosshair.visible = !crosshair.visible,
+            //handled by trace_input and animation_input
+            Some(
+                NavigationAction::ToggleTrace
+                | NavigationAction::ToggleDerivative
+                | NavigationAction::PlayPause
+                | NavigationAction::SpeedUp
+                | NavigationAction::SlowDown
+                | NavigationAction::CycleAnimationMode,
+            )
+            | None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn load(name: &str, source: &str) -> Result<KeyMap, ConfigError> {
+        let path = std::env::temp_dir().join(format!("grapher_{}_{}.ron", name, std::process::id()));
+        fs::write(&path, source).unwrap();
+        let key_map = KeyMap::load(&path);
+        fs::remove_file(&path).unwrap();
+        key_map
+    }
+
+    #[test]
+    fn partial_key_maps_keep_the_other_defaults() {
+        let key_map = load("partial_key_map", "(pan_step: 10, bindings: {Q: ZoomIn})").unwrap();
+        let defaults = KeyMap::default();
+
+        assert_eq!(key_map.pan_step, 10f32);
+        assert_eq!(key_map.zoom_step, defaults.zoom_step);
+        assert_eq!(key_map.wheel_line_zoom, defaults.wheel_line_zoom);
+        assert_eq!(key_map.trace_step, defaults.trace_step);
+        //bindings replace the defaults as a whole
+        assert_eq!(key_map.action(VirtualKeyCode::Q), Some(NavigationAction::ZoomIn));
+        assert_eq!(key_map.action(VirtualKeyCode::Left), None);
+    }
+
+    #[test]
+    fn unknown_keys_are_reported() {
+        let unknown_key = load("unknown_key", "(bindings: {Hyper: ZoomIn})");
+        assert!(matches!(unknown_key, Err(ConfigError::Parse("key map", _))));
+
+        let unknown_field = load("unknown_field", "(pan_stpe: 10)");
+        assert!(matches!(unknown_field, Err(ConfigError::Parse("key map", _))));
+    }
+}
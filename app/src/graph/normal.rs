@@ -1,14 +1,23 @@
 use std::f32::consts::{PI, E};
 
 use cgmath::num_traits::Pow;
+use serde::{Deserialize, Serialize};
 
+use super::Equation;
+use crate::document::EquationDocument;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Normal {
     pub deviation: f32,
     pub mean: f32
 }
 
-impl Normal {
+impl Equation for Normal {
     fn f(&self, x: f32) -> f32 {
         (1f32 / (2f32 * PI * self.deviation).sqrt()) * E.pow(- ((x - self.mean).powf(2f32) / (2f32 * (self.deviation).powf(2f32))))
     }
+
+    fn to_document(&self) -> Option<EquationDocument> {
+        Some(EquationDocument::Normal(self.clone()))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,200 @@
+use bevy_ecs::prelude::*;
+use rendering::{Frame, RenderContext, RenderPhase};
+use two_dimensional::{
+    primitives::{
+        line::{CameraUniform, Line, LineBuffers, LineList, RectPipeline},
+        text::{add_text, text_size},
+    },
+    CameraController, CameraMatrix, Rect, View,
+};
+
+use super::Theme;
+
+//how opaque the inside of the box zoom selection is
+const SELECTION_ALPHA: f32 = 0.2;
+//the selection outline, in pixels
+const SELECTION_WIDTH: f32 = 1f32;
+//the size of one pixel of a label's font, and the room around its text, in logical pixels
+const LABEL_PIXEL: f32 = 2f32;
+const LABEL_MARGIN: f32 = 4f32;
+//how far a label sits from the point it is about
+const LABEL_OFFSET: f32 = 16f32;
+//labels let a little of the graph show through their background
+const LABEL_ALPHA: f32 = 0.85;
+
+//interaction feedback drawn over the whole scene, kept apart from the plots
+//so showing or moving it never touches their geometry
+#[derive(Default)]
+pub struct Overlay {
+    //in graph space
+    pub selection: LineList,
+    //in screen space, physical pixels from the top left of the window
+    pub crosshair: OverlayLayer,
+    pub trace: OverlayLayer,
+    pub sliders: OverlayLayer,
+    pub timeline: OverlayLayer,
+}
+
+impl Overlay {
+    fn lines(&self) -> LineList {
+        self.selection.clone()
+    }
+
+    fn layers(&self) -> [&OverlayLayer; 4] {
+        [&self.crosshair, &self.trace, &self.sliders, &self.timeline]
+    }
+
+    fn screen_lines(&self) -> LineList {
+        let mut lines = LineList::new();
+        for layer in self.layers() {
+            lines.extend(&layer.lines);
+        }
+        lines
+    }
+
+    fn screen_text(&self) -> LineList {
+        let mut text = LineList::new();
+        for layer in self.layers() {
+            text.extend(&layer.text);
+        }
+        text
+    }
+}
+
+//one screen space part of the overlay, its text is drawn in RenderPhase::Text
+//over every overlay's boxes and lines, so no label is hidden by another part
+#[derive(Default, Clone)]
+pub struct OverlayLayer {
+    pub lines: LineList,
+    pub text: LineList,
+}
+
+impl OverlayLayer {
+    pub fn is_empty(&self) -> bool {
+        self.lines.indices().is_empty() && self.text.indices().is_empty()
+    }
+}
+
+//a rectangle in screen space between two opposite corners
+pub fn add_screen_rect(lines: &mut LineList, (x0, y0): (f32, f32), (x1, y1): (f32, f32), color: [f32; 4]) {
+    lines.add_polygon(&[(x0, y0), (x1, y0), (x1, y1), (x0, y1)], color);
+}
+
+//a line of text on a box in screen space, below and to the right of a point,
+//flipped to the other side when it would run off the window
+pub fn add_label(layer: &mut OverlayLayer, text: &str, (x, y): (f32, f32), view: &View, theme: &Theme) {
+    let scale_factor = view.scale_factor as f32;
+    let pixel = LABEL_PIXEL * scale_factor;
+    let margin = LABEL_MARGIN * scale_factor;
+    let offset = LABEL_OFFSET * scale_factor;
+
+    let (text_width, text_height) = text_size(text, pixel);
+    let (box_width, box_height) = (text_width + 2f32 * margin, text_height + 2f32 * margin);
+    let (width, height) = view.screen_size();
+    let left = if x + offset + box_width > width {
+        x - offset - box_width
+    } else {
+        x + offset
+    };
+    let top = if y + offset + box_height > height {
+        y - offset - box_height
+    } else {
+        y + offset
+    };
+
+    let [r, g, b] = theme.background;
+    let (right, bottom) = (left + box_width, top + box_height);
+    add_screen_rect(&mut layer.lines, (left, top), (right, bottom), [r, g, b, LABEL_ALPHA]);
+    let [r, g, b] = theme.label_text;
+    add_text(&mut layer.text, text, (left + margin, top + margin), pixel, [r, g, b, 1f32]);
+}
+
+//the translucent rectangle shown while a box zoom is being dragged
+pub fn update_selection_overlay(
+    cam_controller: Res<CameraController>,
+    view: Res<View>,
+    theme: Res<Theme>,
+    mut overlay: ResMut<Overlay>,
+) {
+    if !cam_controller.is_changed() && !view.is_changed() && !theme.is_changed() {
+        return;
+    }
+
+    let mut lines = LineList::new();
+    if let Some(Rect { left, right, bottom, top }) = cam_controller.selection() {
+        let [r, g, b] = theme.selection;
+        lines.add_polygon(
+            &[(left, bottom), (right, bottom), (right, top), (left, top)],
+            [r, g, b, SELECTION_ALPHA],
+        );
+
+        let corners = [(left, bottom), (right, bottom), (right, top), (left, top), (left, bottom)];
+        for edge in corners.windows(2) {
+            let line = Line {
+                width: SELECTION_WIDTH,
+                start: edge[0],
+                end: edge[1],
+                color: theme.selection,
+            };
+            lines.add_line(&line, &view);
+        }
+    }
+
+    //nothing was selected last frame either, leave the overlay alone so it isn't re-uploaded
+    if lines.indices().is_empty() && overlay.selection.indices().is_empty() {
+        return;
+    }
+    overlay.selection = lines;
+}
+
+//what render_overlay keeps on the gpu between frames
+#[derive(Default)]
+pub struct OverlayBuffers {
+    graph: Option<LineBuffers>,
+    screen: Option<LineBuffers>,
+    text: Option<LineBuffers>,
+    //the screen space parts are drawn with their own matrix
+    screen_uniform: Option<CameraUniform>,
+}
+
+//the graph space part is drawn first, so labels end up over selections, and text goes over both
+pub fn render_overlay(
+    rect_pipeline: Res<RectPipeline>,
+    render_context: Res<RenderContext>,
+    camera_uniform: Res<CameraUniform>,
+    view: Res<View>,
+    overlay: Res<Overlay>,
+    frame: Option<ResMut<Frame>>,
+    mut buffers: Local<OverlayBuffers>,
+) {
+    if overlay.is_changed() {
+        buffers.graph = LineBuffers::new(&render_context, &overlay.lines());
+        buffers.screen = LineBuffers::new(&render_context, &overlay.screen_lines());
+        buffers.text = LineBuffers::new(&render_context, &overlay.screen_text());
+    }
+
+    //the screen matrix only changes when the window is resized
+    match buffers.screen_uniform.as_ref() {
+        Some(uniform) if view.is_changed() => uniform.update(&render_context, CameraMatrix::screen(&view)),
+        Some(_) => {}
+        None => buffers.screen_uniform = Some(CameraUniform::new(&render_context, CameraMatrix::screen(&view))),
+    }
+
+    let mut frame = match frame {
+        Some(frame) => frame,
+        None => return,
+    };
+    if let Some(graph) = buffers.graph.as_ref() {
+        rect_pipeline.draw(&render_context, &camera_uniform, &mut frame, RenderPhase::Overlay, graph);
+    }
+    let screen_uniform = match buffers.screen_uniform.as_ref() {
+        Some(screen_uniform) => screen_uniform,
+        None => return,
+    };
+    if let Some(screen) = buffers.screen.as_ref() {
+        rect_pipeline.draw(&render_context, screen_uniform, &mut frame, RenderPhase::Overlay, screen);
+    }
+    if let Some(text) = buffers.text.as_ref() {
+        rect_pipeline.draw(&render_context, screen_uniform, &mut frame, RenderPhase::Text, text);
+    }
+}
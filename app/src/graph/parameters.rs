@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{EquationBox, Plot};
+
+//a named value formulas can read, along with the range its slider covers
+//a parameter file or document only needs the fields it changes, the rest keep their defaults
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Parameter {
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    //values snap to multiples of this above min, 0 doesn't snap
+    pub step: f32,
+}
+
+impl Default for Parameter {
+    fn default() -> Self {
+        Self {
+            value: 1f32,
+            min: -10f32,
+            max: 10f32,
+            step: 0.1,
+        }
+    }
+}
+
+impl Parameter {
+    //clamp a value into the range and round it to the nearest step
+    pub fn snap(&self, value: f32) -> f32 {
+        let value = value.max(self.min).min(self.max);
+        if self.step <= 0f32 {
+            return value;
+        }
+        let steps = ((value - self.min) / self.step).round();
+        (self.min + steps * self.step).min(self.max)
+    }
+}
+
+//every parameter by name, kept sorted so the sliders have a stable order
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Parameters {
+    parameters: BTreeMap<String, Parameter>,
+}
+
+impl Parameters {
+    pub fn get(&self, name: &str) -> Option<&Parameter> {
+        self.parameters.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Parameter> {
+        self.parameters.get_mut(name)
+    }
+
+    //parameters nobody registered read as the default value
+    pub fn value(&self, name: &str) -> f32 {
+        self.get(name)
+            .map_or(Parameter::default().value, |parameter| parameter.value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.parameters.contains_key(name)
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, parameter: Parameter) {
+        self.parameters.insert(name.into(), parameter);
+    }
+
+    //snaps the value to the parameter's range and step, returns false for unknown names
+    pub fn set(&mut self, name: &str, value: f32) -> bool {
+        match self.parameters.get_mut(name) {
+            Some(parameter) => {
+                parameter.value = parameter.snap(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Parameter)> {
+        self.parameters
+            .iter()
+            .map(|(name, parameter)| (name.as_str(), parameter))
+    }
+
+    pub fn len(&self) -> usize {
+        self.parameters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parameters.is_empty()
+    }
+}
+
+//new formulas get a default parameter for every name they read that doesn't exist yet,
+//so each one shows up with a slider
+pub fn register_parameters(mut parameters: ResMut<Parameters>, plots: Query<&Plot, Changed<Plot>>) {
+    for plot in &plots {
+        let formula = match plot.equation().and_then(EquationBox::formula) {
+            Some(formula) => formula,
+            None => continue,
+        };
+        for name in formula.parameters() {
+            if !parameters.contains(name) {
+                parameters.insert(name.clone(), Parameter::default());
+            }
+        }
+    }
+}
+
+//hand the parameter values to the equations that read them
+//only the equations whose values differ from what they were bound to are touched,
+//so moving one slider only regenerates the curves that use that parameter
+pub fn bind_parameters(parameters: Res<Parameters>, mut plots: Query<&mut Plot>) {
+    for mut plot in &mut plots {
+        if !parameters.is_changed() && !plot.is_changed() {
+            continue;
+        }
+        let values: Vec<f32> = match plot.equation().and_then(EquationBox::formula) {
+            Some(formula) if !formula.parameters().is_empty() => {
+                let values: Vec<f32> = formula
+                    .parameters()
+                    .iter()
+                    .map(|name| parameters.value(name))
+                    .collect();
+                if values == formula.bound() {
+                    continue;
+                }
+                values
+            }
+            _ => continue,
+        };
+        if let Some(formula) = plot.equation_mut().and_then(EquationBox::formula_mut) {
+            formula.bind(&values);
+        }
+    }
+}
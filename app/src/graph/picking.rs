@@ -0,0 +1,258 @@
+use bevy_ecs::prelude::*;
+use two_dimensional::View;
+use winit::dpi::PhysicalPosition;
+
+use super::{Cursor, Geometry, Shapes, Visibility, ZOrder};
+
+//how close the cursor has to come to a plot to pick it
+pub struct PickSettings {
+    //in logical pixels, from the middle of a line or the center of a point
+    pub tolerance: f32,
+}
+
+impl Default for PickSettings {
+    fn default() -> Self {
+        Self { tolerance: 6f32 }
+    }
+}
+
+//a plotted entity under the cursor and the point on it nearest the cursor, in graph space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pick {
+    pub entity: Entity,
+    pub point: (f32, f32),
+    //from the cursor to the point, in physical pixels, zero inside a filled region
+    pub distance: f32,
+}
+
+//sent whenever what is under the cursor changes, including the point sliding along the same plot
+//and the cursor leaving every plot, which is a None
+pub struct Hovered {
+    pub pick: Option<Pick>,
+}
+
+//the latest pick, for systems that only care about what is under the cursor right now
+#[derive(Default)]
+pub struct Hover {
+    pub pick: Option<Pick>,
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+//the closest point on a segment, everything in screen space
+fn closest_on_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0f32 {
+        return a;
+    }
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0f32, 1f32);
+    (a.0 + t * dx, a.1 + t * dy)
+}
+
+//even odd rule, so it works for any simple polygon
+fn polygon_contains(polygon: &[(f32, f32)], (x, y): (f32, f32)) -> bool {
+    let mut inside = false;
+    let mut previous = match polygon.last() {
+        Some(previous) => *previous,
+        None => return false,
+    };
+    for &point in polygon {
+        if (point.1 > y) != (previous.1 > y)
+            && x < (previous.0 - point.0) * (y - point.1) / (previous.1 - point.1) + point.0
+        {
+            inside = !inside;
+        }
+        previous = point;
+    }
+    inside
+}
+
+impl Shapes {
+    //the graph space point nearest the cursor and how far it is in physical pixels
+    //distances are measured on screen so the tolerance holds when x and y are scaled differently
+    pub fn nearest(&self, view: &View, cursor: PhysicalPosition<f64>) -> Option<((f32, f32), f32)> {
+        let target = view.screen_to_graph(cursor);
+        if self.polygons.iter().any(|polygon| polygon_contains(polygon, target)) {
+            return Some((target, 0f32));
+        }
+
+        let to_screen = |point: (f32, f32)| {
+            let position = view.graph_to_screen(point);
+            (position.x as f32, position.y as f32)
+        };
+        let cursor = (cursor.x as f32, cursor.y as f32);
+        let on_segments = self
+            .segments
+            .iter()
+            .map(|&(start, end)| closest_on_segment(cursor, to_screen(start), to_screen(end)));
+        let on_points = self.points.iter().map(|&point| to_screen(point));
+
+        on_segments
+            .chain(on_points)
+            .map(|point| (point, distance(cursor, point)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|((x, y), distance)| {
+                let point = view.screen_to_graph(PhysicalPosition::new(x as f64, y as f64));
+                (point, distance)
+            })
+    }
+}
+
+//the visible plot nearest the cursor within the tolerance, in physical pixels
+//when several are as close, like overlapping fills, the one drawn on top wins
+pub fn pick<'a>(
+    view: &View,
+    cursor: PhysicalPosition<f64>,
+    tolerance: f32,
+    plots: impl IntoIterator<Item = (Entity, &'a Geometry, &'a Visibility, &'a ZOrder)>,
+) -> Option<Pick> {
+    plots
+        .into_iter()
+        .filter(|(_, _, visibility, _)| visibility.visible)
+        .filter_map(|(entity, geometry, _, z_order)| {
+            let (point, distance) = geometry.shapes.nearest(view, cursor)?;
+            (distance <= tolerance).then_some((Pick { entity, point, distance }, *z_order))
+        })
+        .min_by(|(a, a_order), (b, b_order)| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then((*b_order, b.entity).cmp(&(*a_order, a.entity)))
+        })
+        .map(|(pick, _)| pick)
+}
+
+type PickableChanged = Or<(Changed<Geometry>, Changed<Visibility>)>;
+
+//pick again whenever the cursor, the view or any plot's geometry changed
+pub fn hover_pick(
+    cursor: Res<Cursor>,
+    view: Res<View>,
+    settings: Res<PickSettings>,
+    plots: Query<(Entity, &Geometry, &Visibility, &ZOrder)>,
+    changed: Query<(), PickableChanged>,
+    mut hover: ResMut<Hover>,
+    mut events: EventWriter<Hovered>,
+) {
+    if !cursor.is_changed() && !view.is_changed() && !settings.is_changed() && changed.is_empty() {
+        return;
+    }
+
+    let new_pick = cursor.position.and_then(|position| {
+        let tolerance = settings.tolerance * view.scale_factor as f32;
+        pick(&view, position, tolerance, &plots)
+    });
+    if hover.pick != new_pick {
+        hover.pick = new_pick;
+        events.send(Hovered { pick: new_pick });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use two_dimensional::CameraController;
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+
+    fn view() -> View {
+        CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600)).view()
+    }
+
+    fn point_at(point: (f32, f32)) -> Geometry {
+        let mut geometry = Geometry::default();
+        geometry.shapes.points.push(point);
+        geometry
+    }
+
+    //a screen position this many pixels right of a graph point
+    fn right_of(view: &View, point: (f32, f32), pixels: f64) -> PhysicalPosition<f64> {
+        let position = view.graph_to_screen(point);
+        PhysicalPosition::new(position.x + pixels, position.y)
+    }
+
+    const SHOWN: Visibility = Visibility { visible: true };
+    const HIDDEN: Visibility = Visibility { visible: false };
+
+    #[test]
+    fn picks_only_within_the_tolerance() {
+        let view = view();
+        let entity = World::new().spawn().id();
+        let geometry = point_at((1f32, 1f32));
+        let cursor = right_of(&view, (1f32, 1f32), 5.0);
+        let plots = [(entity, &geometry, &SHOWN, &ZOrder(0))];
+
+        let picked = pick(&view, cursor, 6f32, plots).unwrap();
+        assert_eq!(picked.entity, entity);
+        assert!((picked.distance - 5f32).abs() < 1e-3);
+        assert!((picked.point.0 - 1f32).abs() < 1e-4 && (picked.point.1 - 1f32).abs() < 1e-4);
+
+        assert_eq!(pick(&view, cursor, 4f32, plots), None);
+    }
+
+    #[test]
+    fn segments_are_measured_to_their_nearest_point() {
+        let view = view();
+        let entity = World::new().spawn().id();
+        let mut geometry = Geometry::default();
+        geometry.shapes.segments.push(((-1f32, 0f32), (1f32, 0f32)));
+        let cursor = view.graph_to_screen((0.5, 0f32));
+        let cursor = PhysicalPosition::new(cursor.x, cursor.y + 3.0);
+
+        let picked = pick(&view, cursor, 6f32, [(entity, &geometry, &SHOWN, &ZOrder(0))]).unwrap();
+        assert!((picked.distance - 3f32).abs() < 1e-3);
+        assert!((picked.point.0 - 0.5).abs() < 1e-4 && picked.point.1.abs() < 1e-4);
+    }
+
+    #[test]
+    fn ties_go_to_the_plot_drawn_on_top() {
+        let view = view();
+        let mut world = World::new();
+        let (first, second, third) = (world.spawn().id(), world.spawn().id(), world.spawn().id());
+        let geometry = point_at((0f32, 0f32));
+        let cursor = right_of(&view, (0f32, 0f32), 2.0);
+
+        //the highest z order wins wherever it was spawned
+        let plots = [
+            (first, &geometry, &SHOWN, &ZOrder(3)),
+            (second, &geometry, &SHOWN, &ZOrder(1)),
+            (third, &geometry, &SHOWN, &ZOrder(2)),
+        ];
+        assert_eq!(pick(&view, cursor, 6f32, plots).unwrap().entity, first);
+
+        //the same z order is drawn in spawn order, so the last one spawned is on top
+        let plots = [
+            (second, &geometry, &SHOWN, &ZOrder(0)),
+            (first, &geometry, &SHOWN, &ZOrder(0)),
+        ];
+        assert_eq!(pick(&view, cursor, 6f32, plots).unwrap().entity, second);
+
+        //but a closer plot beats one on top
+        let closer = point_at((0f32, 0f32));
+        let farther = point_at((-1f32 / view.scale.0, 0f32));
+        let plots = [
+            (first, &closer, &SHOWN, &ZOrder(0)),
+            (second, &farther, &SHOWN, &ZOrder(5)),
+        ];
+        assert_eq!(pick(&view, cursor, 6f32, plots).unwrap().entity, first);
+    }
+
+    #[test]
+    fn hidden_plots_are_skipped() {
+        let view = view();
+        let mut world = World::new();
+        let (hidden, shown) = (world.spawn().id(), world.spawn().id());
+        let under_cursor = point_at((0f32, 0f32));
+        let nearby = point_at((4f32 / view.scale.0, 0f32));
+        let cursor = view.graph_to_screen((0f32, 0f32));
+
+        let plots = [
+            (hidden, &under_cursor, &HIDDEN, &ZOrder(1)),
+            (shown, &nearby, &SHOWN, &ZOrder(0)),
+        ];
+        assert_eq!(pick(&view, cursor, 6f32, plots).unwrap().entity, shown);
+        assert_eq!(pick(&view, cursor, 6f32, [(hidden, &under_cursor, &HIDDEN, &ZOrder(1))]), None);
+    }
+}
@@ -0,0 +1,358 @@
+use std::any::Any;
+
+use bevy_ecs::prelude::*;
+use two_dimensional::{
+    primitives::line::{Line, LineList},
+    Rect, View,
+};
+
+use super::{AutoColor, EquationBox, Fill, Geometry, Shapes, Stroke, Visibility, ZOrder};
+use crate::document::PlotDocument;
+
+//lets a boxed plottable be turned back into its concrete type
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+//anything that can draw itself for a given view, equations, data series, shapes, annotations
+//to add a new kind of plotted object implement this and spawn it with a PlotBundle,
+//the renderer never needs to know about the concrete type
+pub trait Plottable: AsAny + Send + Sync {
+    fn generate(&self, view: &View, out: &mut SceneBuilder);
+
+    //the area the plot covers, for fitting the view to it
+    //plots that go on forever only report the part over the domain, which is a range of x
+    fn bounds(&self, _domain: (f32, f32)) -> Option<Rect> {
+        None
+    }
+
+    //take new positions from the control points this plot is driven by, in the order DrivenBy lists them
+    //plots that don't depend on any points ignore this
+    fn drive(&mut self, _points: &[(f32, f32)]) {}
+
+    //the equation the plot draws, or is built on like a tangent line's,
+    //so the formulas in it are linked and bound the same as a plotted one
+    fn equation(&self) -> Option<&EquationBox> {
+        None
+    }
+
+    fn equation_mut(&mut self) -> Option<&mut EquationBox> {
+        None
+    }
+
+    //the form the plot is saved in, the document adds the style it's drawn with
+    //plots that can't be saved return None and are left out of documents
+    fn to_document(&self) -> Option<PlotDocument> {
+        None
+    }
+}
+
+//the component every plotted entity carries
+#[derive(Component)]
+pub struct Plot {
+    plottable: Box<dyn Plottable>,
+}
+
+impl Plot {
+    pub fn new(plottable: impl Plottable + 'static) -> Self {
+        Self {
+            plottable: Box::new(plottable),
+        }
+    }
+
+    pub fn generate(&self, view: &View, out: &mut SceneBuilder) {
+        self.plottable.generate(view, out);
+    }
+
+    pub fn bounds(&self, domain: (f32, f32)) -> Option<Rect> {
+        self.plottable.bounds(domain)
+    }
+
+    pub fn drive(&mut self, points: &[(f32, f32)]) {
+        self.plottable.drive(points);
+    }
+
+    pub fn equation(&self) -> Option<&EquationBox> {
+        self.plottable.equation()
+    }
+
+    pub fn equation_mut(&mut self) -> Option<&mut EquationBox> {
+        self.plottable.equation_mut()
+    }
+
+    pub fn to_document(&self) -> Option<PlotDocument> {
+        self.plottable.to_document()
+    }
+
+    pub fn downcast_ref<T: Plottable + 'static>(&self) -> Option<&T> {
+        self.plottable.as_ref().as_any().downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Plottable + 'static>(&mut self) -> Option<&mut T> {
+        self.plottable.as_mut().as_any_mut().downcast_mut()
+    }
+}
+
+//everything a plotted entity needs to be drawn
+//new plots take their stroke color from the palette unless one is given
+#[derive(Bundle)]
+pub struct PlotBundle {
+    pub plot: Plot,
+    pub stroke: Stroke,
+    pub fill: Fill,
+    pub visibility: Visibility,
+    pub z_order: ZOrder,
+    pub auto_color: AutoColor,
+}
+
+impl PlotBundle {
+    pub fn new(plottable: impl Plottable + 'static) -> Self {
+        Self {
+            plot: Plot::new(plottable),
+            stroke: Stroke::default(),
+            fill: Fill::default(),
+            visibility: Visibility::default(),
+            z_order: ZOrder::default(),
+            auto_color: AutoColor(true),
+        }
+    }
+
+    pub fn with_stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = stroke;
+        self.auto_color = AutoColor(false);
+        self
+    }
+
+    pub fn with_fill(mut self, fill: Fill) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn with_z_order(mut self, z_order: ZOrder) -> Self {
+        self.z_order = z_order;
+        self
+    }
+}
+
+//what a plottable draws into, shapes are given in graph space and styled with the entity's stroke and fill
+//fills always end up under the lines of the same plot
+pub struct SceneBuilder<'a> {
+    view: &'a View,
+    stroke: Stroke,
+    fill: Fill,
+    fills: LineList,
+    lines: LineList,
+    shapes: Shapes,
+}
+
+impl<'a> SceneBuilder<'a> {
+    pub fn new(view: &'a View, stroke: Stroke, fill: Fill) -> Self {
+        Self {
+            view,
+            stroke,
+            fill,
+            fills: LineList::new(),
+            lines: LineList::new(),
+            shapes: Shapes::default(),
+        }
+    }
+
+    pub fn stroke(&self) -> &Stroke {
+        &self.stroke
+    }
+
+    pub fn fill(&self) -> Option<[f32; 3]> {
+        self.fill.color
+    }
+
+    //a convex polygon filled with the entity's fill, does nothing if the entity isn't filled
+    pub fn fill_polygon(&mut self, points: &[(f32, f32)]) {
+        if let Some([r, g, b]) = self.fill.color {
+            self.fills.add_polygon(points, [r, g, b, 1f32]);
+            self.shapes.polygons.push(points.to_vec());
+        }
+    }
+
+    pub fn line(&mut self, start: (f32, f32), end: (f32, f32)) {
+        let line = Line {
+            width: self.stroke.width,
+            start,
+            end,
+            color: self.stroke.color,
+        };
+        self.add_line(&line);
+    }
+
+    //a square dot twice as wide as the stroke, centered on the point
+    pub fn point(&mut self, (x, y): (f32, f32)) {
+        let half_width = self.stroke.width * self.view.aspect.0;
+        let half_height = self.stroke.width * self.view.aspect.1;
+        let [r, g, b] = self.stroke.color;
+        self.lines.add_polygon(
+            &[
+                (x - half_width, y - half_height),
+                (x + half_width, y - half_height),
+                (x + half_width, y + half_height),
+                (x - half_width, y + half_height),
+            ],
+            [r, g, b, 1f32],
+        );
+        self.shapes.points.push((x, y));
+    }
+
+    //connect each point to the next one
+    pub fn polyline(&mut self, points: impl IntoIterator<Item = (f32, f32)>) {
+        let mut points = points.into_iter();
+        if let Some(mut start) = points.next() {
+            for end in points {
+                self.line(start, end);
+                start = end;
+            }
+        }
+    }
+
+    //for lines that don't follow the entity's stroke
+    pub fn add_line(&mut self, line: &Line) {
+        self.lines.add_line(line, self.view);
+        self.shapes.segments.push((line.start, line.end));
+    }
+
+    pub fn finish(self) -> Geometry {
+        let mut lines = self.fills;
+        lines.extend(&self.lines);
+        Geometry {
+            lines,
+            shapes: self.shapes,
+        }
+    }
+}
+
+type StyledPlot = (
+    Entity,
+    &'static Plot,
+    &'static Stroke,
+    &'static Fill,
+    &'static Visibility,
+    &'static mut Geometry,
+);
+
+type StylingChanged = Or<(Changed<Plot>, Changed<Stroke>, Changed<Fill>, Changed<Visibility>)>;
+
+//how many plots each task on the compute pool generates, plots range from a single point to a filled
+//curve sampled at every pixel, so batching them would leave a few slow curves on one thread while
+//the rest sit idle, and spawning a task is cheap next to sampling even a short curve
+const PLOTS_PER_TASK: usize = 1;
+
+//the one system that turns plotted entities into lines
+//a plot is only regenerated when the view, the plot or its style changed
+//plots are generated in parallel on the ComputeTaskPool, each into its own Geometry,
+//and collect_lines puts them together in z order so the result doesn't depend on which finished first
+pub fn generate_plots(
+    mut query: Query<StyledPlot>,
+    changed: Query<(), StylingChanged>,
+    view: Res<View>,
+) {
+    let view_changed = view.is_changed();
+    let view = &*view;
+    let changed = &changed;
+    query.par_for_each_mut(
+        PLOTS_PER_TASK,
+        move |(entity, plot, stroke, fill, visibility, mut geometry)| {
+            //hidden plots keep their old lines, showing them again counts as a change
+            if !visibility.visible {
+                return;
+            }
+
+            let stale = view_changed || changed.get(entity).is_ok() || geometry.is_added();
+            if !stale {
+                return;
+            }
+
+            let mut out = SceneBuilder::new(view, stroke.clone(), fill.clone());
+            plot.generate(view, &mut out);
+            *geometry = out.finish();
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_tasks::{ComputeTaskPool, TaskPool};
+    use two_dimensional::CameraController;
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::expression::Expression;
+    use crate::graph::{Circle, Formula, TangentLine};
+
+    fn formula(source: &str) -> EquationBox {
+        EquationBox::new(Formula::new(Expression::parse(source).unwrap()))
+    }
+
+    fn same_lines(a: &LineList, b: &LineList) -> bool {
+        let bytes = |lines: &LineList| bytemuck::cast_slice::<_, u8>(lines.vertices()).to_vec();
+        bytes(a) == bytes(b) && a.indices() == b.indices()
+    }
+
+    #[test]
+    fn parallel_generation_matches_serial() {
+        ComputeTaskPool::init(TaskPool::default);
+        let mut world = World::new();
+        let view = CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600)).view();
+        world.insert_resource(view.clone());
+
+        let filled = Fill {
+            color: Some([0.2, 0.4, 0.6]),
+        };
+        let bundles = [
+            PlotBundle::new(formula("sin(x)")),
+            PlotBundle::new(formula("x^2 - 3")).with_fill(filled.clone()),
+            PlotBundle::new(formula("1 / x")),
+            PlotBundle::new(formula("sqrt(x) * cos(4x)")).with_fill(filled),
+            PlotBundle::new(formula("floor(x)")),
+            PlotBundle::new(Circle::new((1f32, -1f32), 2f32)),
+            PlotBundle::new(TangentLine::new(formula("x^3 - x"), 0.5)),
+        ];
+        let entities: Vec<Entity> = bundles
+            .into_iter()
+            .map(|bundle| world.spawn().insert_bundle(bundle).insert(Geometry::default()).id())
+            .collect();
+
+        SystemStage::single_threaded()
+            .with_system(generate_plots)
+            .run(&mut world);
+
+        for entity in entities {
+            let mut out = SceneBuilder::new(
+                &view,
+                world.get::<Stroke>(entity).unwrap().clone(),
+                world.get::<Fill>(entity).unwrap().clone(),
+            );
+            world.get::<Plot>(entity).unwrap().generate(&view, &mut out);
+            let serial = out.finish();
+
+            let parallel = world.get::<Geometry>(entity).unwrap();
+            assert!(!parallel.lines.vertices().is_empty());
+            assert!(same_lines(&parallel.lines, &serial.lines));
+            assert_eq!(parallel.shapes.segments, serial.shapes.segments);
+            assert_eq!(parallel.shapes.points, serial.shapes.points);
+            assert_eq!(parallel.shapes.polygons, serial.shapes.polygons);
+        }
+    }
+}
@@ -0,0 +1,543 @@
+use bevy_ecs::{prelude::*, system::SystemParam};
+use two_dimensional::View;
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, ModifiersState, MouseButton, WindowEvent},
+};
+
+use super::{pick, Geometry, Hovered, PickSettings, Visibility, ZOrder};
+
+//how far the cursor can move between press and release for it to still count as a click, in logical pixels
+const CLICK_SLOP: f64 = 4.0;
+
+//entities with this take a left drag that starts on them, so the camera doesn't pan
+#[derive(Component, Default)]
+pub struct Draggable;
+
+//a press and release on an entity without moving in between
+pub struct Clicked {
+    pub entity: Entity,
+    pub button: MouseButton,
+    //in graph space, where the button was released
+    pub point: (f32, f32),
+}
+
+//the rest are only sent for draggable entities, in this order
+pub struct DragStarted {
+    pub entity: Entity,
+    //where the drag was started, which is usually a little behind the cursor
+    pub point: (f32, f32),
+}
+
+pub struct Dragged {
+    pub entity: Entity,
+    pub point: (f32, f32),
+    //how far the cursor moved since the last Dragged or DragStarted, in graph space
+    pub delta: (f32, f32),
+}
+
+pub struct DragEnded {
+    pub entity: Entity,
+    pub point: (f32, f32),
+}
+
+pub struct HoverEnter {
+    pub entity: Entity,
+}
+
+pub struct HoverExit {
+    pub entity: Entity,
+}
+
+//an area of the window that takes left presses before any entity, like a slider
+//in physical pixels from the top left of the window
+pub struct Widget {
+    pub id: String,
+    pub min: (f32, f32),
+    pub max: (f32, f32),
+}
+
+//every widget on screen, each kind of widget keeps its own regions up to date
+#[derive(Default)]
+pub struct Widgets {
+    pub regions: Vec<Widget>,
+}
+
+impl Widgets {
+    //the last region added is drawn on top, so it wins
+    pub fn at(&self, position: PhysicalPosition<f64>) -> Option<&Widget> {
+        let (x, y) = (position.x as f32, position.y as f32);
+        self.regions
+            .iter()
+            .rev()
+            .find(|widget| x >= widget.min.0 && x <= widget.max.0 && y >= widget.min.1 && y <= widget.max.1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetPhase {
+    Pressed,
+    Dragged,
+    Released,
+}
+
+//the left button pressed on a widget, and every move until it is released, wherever the cursor goes
+pub struct WidgetInput {
+    pub id: String,
+    pub phase: WidgetPhase,
+    pub position: PhysicalPosition<f64>,
+}
+
+//a mouse button held down over an entity
+struct Press {
+    entity: Entity,
+    button: MouseButton,
+    start: PhysicalPosition<f64>,
+    //the camera never saw the press, so the drag belongs to the entity
+    captured: bool,
+    //the graph space point the last drag event was sent at, None until the drag starts
+    last_point: Option<(f32, f32)>,
+}
+
+//what the router remembers between events
+#[derive(Default)]
+pub struct Pointer {
+    position: Option<PhysicalPosition<f64>>,
+    modifiers: ModifiersState,
+    press: Option<Press>,
+    //the widget holding the left button
+    widget: Option<String>,
+}
+
+impl Pointer {
+    //the entity the current drag belongs to, if an entity took it
+    pub fn captured(&self) -> Option<Entity> {
+        self.press
+            .as_ref()
+            .filter(|press| press.captured)
+            .map(|press| press.entity)
+    }
+
+    pub fn widget(&self) -> Option<&str> {
+        self.widget.as_deref()
+    }
+}
+
+type Pickable = (Entity, &'static Geometry, &'static Visibility, &'static ZOrder);
+
+//turns mouse events into events targeted at the entity under the cursor
+//the camera only gets the events the router didn't consume, see camera_input
+#[derive(SystemParam)]
+pub struct PointerRouter<'w, 's> {
+    pointer: ResMut<'w, Pointer>,
+    view: Res<'w, View>,
+    settings: Res<'w, PickSettings>,
+    widgets: Res<'w, Widgets>,
+    plots: Query<'w, 's, Pickable>,
+    draggable: Query<'w, 's, (), With<Draggable>>,
+    clicked: EventWriter<'w, 's, Clicked>,
+    drag_started: EventWriter<'w, 's, DragStarted>,
+    dragged: EventWriter<'w, 's, Dragged>,
+    drag_ended: EventWriter<'w, 's, DragEnded>,
+    widget_input: EventWriter<'w, 's, WidgetInput>,
+}
+
+impl<'w, 's> PointerRouter<'w, 's> {
+    fn entity_at(&self, position: PhysicalPosition<f64>) -> Option<Entity> {
+        let tolerance = self.settings.tolerance * self.view.scale_factor as f32;
+        pick(&self.view, position, tolerance, &self.plots).map(|pick| pick.entity)
+    }
+
+    //returns true if the event was consumed and shouldn't reach the camera
+    pub fn route(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.pointer.modifiers = *modifiers;
+                false
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.pointer.position = Some(*position);
+                if let Some(id) = self.pointer.widget.clone() {
+                    self.widget_input.send(WidgetInput {
+                        id,
+                        phase: WidgetPhase::Dragged,
+                        position: *position,
+                    });
+                }
+                self.drag(*position);
+                false
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.pointer.position = None;
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => self.press(*button),
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button,
+                ..
+            } => self.release(*button),
+            _ => false,
+        }
+    }
+
+    fn press(&mut self, button: MouseButton) -> bool {
+        let start = match self.pointer.position {
+            Some(position) => position,
+            None => return false,
+        };
+
+        //widgets sit over the graph, so they are checked first
+        if button == MouseButton::Left {
+            if let Some(widget) = self.widgets.at(start) {
+                let id = widget.id.clone();
+                self.pointer.press = None;
+                self.pointer.widget = Some(id.clone());
+                self.widget_input.send(WidgetInput {
+                    id,
+                    phase: WidgetPhase::Pressed,
+                    position: start,
+                });
+                return true;
+            }
+        }
+
+        let entity = match self.entity_at(start) {
+            Some(entity) => entity,
+            None => {
+                self.pointer.press = None;
+                return false;
+            }
+        };
+
+        //ctrl and left drag is a box zoom, which stays with the camera
+        let captured = button == MouseButton::Left
+            && !self.pointer.modifiers.ctrl()
+            && self.draggable.get(entity).is_ok();
+        self.pointer.press = Some(Press {
+            entity,
+            button,
+            start,
+            captured,
+            last_point: None,
+        });
+        captured
+    }
+
+    fn drag(&mut self, position: PhysicalPosition<f64>) {
+        let slop = CLICK_SLOP * self.view.scale_factor;
+        let point = self.view.screen_to_graph(position);
+        let press = match self.pointer.press.as_mut() {
+            Some(press) if press.captured => press,
+            _ => return,
+        };
+
+        let last_point = match press.last_point {
+            Some(last_point) => last_point,
+            None => {
+                let (dx, dy) = (position.x - press.start.x, position.y - press.start.y);
+                if (dx * dx + dy * dy).sqrt() < slop {
+                    return;
+                }
+                let start = self.view.screen_to_graph(press.start);
+                self.drag_started.send(DragStarted {
+                    entity: press.entity,
+                    point: start,
+                });
+                start
+            }
+        };
+        press.last_point = Some(point);
+        self.dragged.send(Dragged {
+            entity: press.entity,
+            point,
+            delta: (point.0 - last_point.0, point.1 - last_point.1),
+        });
+    }
+
+    fn release(&mut self, button: MouseButton) -> bool {
+        if button == MouseButton::Left {
+            if let Some(id) = self.pointer.widget.take() {
+                let position = self.pointer.position.unwrap_or_default();
+                self.widget_input.send(WidgetInput {
+                    id,
+                    phase: WidgetPhase::Released,
+                    position,
+                });
+                return true;
+            }
+        }
+
+        let press = match self.pointer.press.take() {
+            Some(press) if press.button == button => press,
+            other => {
+                self.pointer.press = other;
+                return false;
+            }
+        };
+        let position = self.pointer.position.unwrap_or(press.start);
+        let point = self.view.screen_to_graph(position);
+
+        if press.last_point.is_some() {
+            self.drag_ended.send(DragEnded {
+                entity: press.entity,
+                point,
+            });
+        } else {
+            let (dx, dy) = (position.x - press.start.x, position.y - press.start.y);
+            //a release after the camera was dragged isn't a click
+            if (dx * dx + dy * dy).sqrt() < CLICK_SLOP * self.view.scale_factor {
+                self.clicked.send(Clicked {
+                    entity: press.entity,
+                    button,
+                    point,
+                });
+            }
+        }
+        press.captured
+    }
+}
+
+//enter and exit events from the picks, an exit always comes before the next enter
+pub fn route_hover(
+    mut hovered: EventReader<Hovered>,
+    mut current: Local<Option<Entity>>,
+    mut enter: EventWriter<HoverEnter>,
+    mut exit: EventWriter<HoverExit>,
+) {
+    for event in hovered.iter() {
+        let entity = event.pick.map(|pick| pick.entity);
+        if entity == *current {
+            continue;
+        }
+        if let Some(entity) = *current {
+            exit.send(HoverExit { entity });
+        }
+        if let Some(entity) = entity {
+            enter.send(HoverEnter { entity });
+        }
+        *current = entity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use two_dimensional::CameraController;
+    use winit::dpi::PhysicalSize;
+    use winit::event::DeviceId;
+
+    use super::*;
+
+    //the events the router is fed on the next run, and whether it consumed each of them
+    #[derive(Default)]
+    struct Script {
+        events: Vec<WindowEvent<'static>>,
+        consumed: Vec<bool>,
+    }
+
+    fn play(mut script: ResMut<Script>, mut router: PointerRouter) {
+        let events = std::mem::take(&mut script.events);
+        script.consumed = events.iter().map(|event| router.route(event)).collect();
+    }
+
+    //only ever compared against other ids, never handed to the platform
+    fn device_id() -> DeviceId {
+        unsafe { DeviceId::dummy() }
+    }
+
+    #[allow(deprecated)]
+    fn move_to(position: PhysicalPosition<f64>) -> WindowEvent<'static> {
+        WindowEvent::CursorMoved {
+            device_id: device_id(),
+            position,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    #[allow(deprecated)]
+    fn mouse(state: ElementState, button: MouseButton) -> WindowEvent<'static> {
+        WindowEvent::MouseInput {
+            device_id: device_id(),
+            state,
+            button,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    fn press(button: MouseButton) -> WindowEvent<'static> {
+        mouse(ElementState::Pressed, button)
+    }
+
+    fn release(button: MouseButton) -> WindowEvent<'static> {
+        mouse(ElementState::Released, button)
+    }
+
+    //a draggable point at the origin, which is the middle of the window
+    fn world() -> (World, Entity, PhysicalPosition<f64>) {
+        let mut world = World::new();
+        let view = CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600)).view();
+        let origin = view.graph_to_screen((0f32, 0f32));
+        world.insert_resource(view);
+        world.insert_resource(Pointer::default());
+        world.insert_resource(PickSettings::default());
+        world.insert_resource(Widgets::default());
+        world.insert_resource(Events::<Clicked>::default());
+        world.insert_resource(Events::<DragStarted>::default());
+        world.insert_resource(Events::<Dragged>::default());
+        world.insert_resource(Events::<DragEnded>::default());
+        world.insert_resource(Events::<WidgetInput>::default());
+        world.insert_resource(Script::default());
+
+        let mut geometry = Geometry::default();
+        geometry.shapes.points.push((0f32, 0f32));
+        let point = world
+            .spawn()
+            .insert_bundle((geometry, Visibility { visible: true }, ZOrder(0), Draggable))
+            .id();
+        (world, point, origin)
+    }
+
+    fn route(world: &mut World, events: Vec<WindowEvent<'static>>) -> Vec<bool> {
+        world.resource_mut::<Script>().events = events;
+        SystemStage::single_threaded().with_system(play).run(world);
+        std::mem::take(&mut world.resource_mut::<Script>().consumed)
+    }
+
+    fn drain<T: Send + Sync + 'static>(world: &mut World) -> Vec<T> {
+        world.resource_mut::<Events<T>>().drain().collect()
+    }
+
+    fn offset(position: PhysicalPosition<f64>, dx: f64) -> PhysicalPosition<f64> {
+        PhysicalPosition::new(position.x + dx, position.y)
+    }
+
+    #[test]
+    fn small_moves_are_still_clicks() {
+        let (mut world, point, origin) = world();
+        let consumed = route(
+            &mut world,
+            vec![
+                move_to(origin),
+                press(MouseButton::Left),
+                move_to(offset(origin, 2.0)),
+                release(MouseButton::Left),
+            ],
+        );
+        assert_eq!(consumed, vec![false, true, false, true]);
+
+        let clicked = drain::<Clicked>(&mut world);
+        assert_eq!(clicked.len(), 1);
+        assert_eq!(clicked[0].entity, point);
+        assert_eq!(clicked[0].button, MouseButton::Left);
+        assert!(drain::<DragStarted>(&mut world).is_empty());
+        assert!(drain::<Dragged>(&mut world).is_empty());
+        assert!(drain::<DragEnded>(&mut world).is_empty());
+    }
+
+    #[test]
+    fn moves_past_the_slop_drag() {
+        let (mut world, point, origin) = world();
+        route(
+            &mut world,
+            vec![
+                move_to(origin),
+                press(MouseButton::Left),
+                move_to(offset(origin, 2.0)),
+                move_to(offset(origin, 10.0)),
+                move_to(offset(origin, 20.0)),
+                release(MouseButton::Left),
+            ],
+        );
+
+        let started = drain::<DragStarted>(&mut world);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].entity, point);
+        let (x, y) = started[0].point;
+        assert!(x.abs() < 1e-4 && y.abs() < 1e-4, "started at ({}, {})", x, y);
+        let dragged = drain::<Dragged>(&mut world);
+        assert_eq!(dragged.len(), 2);
+        assert!(dragged.iter().all(|dragged| dragged.entity == point && dragged.delta.0 > 0f32));
+        assert_eq!(drain::<DragEnded>(&mut world).len(), 1);
+        assert!(drain::<Clicked>(&mut world).is_empty());
+        assert_eq!(world.resource::<Pointer>().captured(), None);
+    }
+
+    #[test]
+    fn widgets_take_presses_before_entities() {
+        let (mut world, _, origin) = world();
+        world.resource_mut::<Widgets>().regions.push(Widget {
+            id: "slider".to_string(),
+            min: (origin.x as f32 - 50f32, origin.y as f32 - 10f32),
+            max: (origin.x as f32 + 50f32, origin.y as f32 + 10f32),
+        });
+
+        let consumed = route(
+            &mut world,
+            vec![
+                move_to(origin),
+                press(MouseButton::Left),
+                //the widget keeps the drag when the cursor leaves it
+                move_to(offset(origin, 100.0)),
+                release(MouseButton::Left),
+            ],
+        );
+        assert_eq!(consumed, vec![false, true, false, true]);
+
+        let phases: Vec<WidgetPhase> = drain::<WidgetInput>(&mut world)
+            .into_iter()
+            .map(|input| {
+                assert_eq!(input.id, "slider");
+                input.phase
+            })
+            .collect();
+        assert_eq!(phases, vec![WidgetPhase::Pressed, WidgetPhase::Dragged, WidgetPhase::Released]);
+        assert!(drain::<Clicked>(&mut world).is_empty());
+        assert!(drain::<DragStarted>(&mut world).is_empty());
+        assert_eq!(world.resource::<Pointer>().widget(), None);
+    }
+
+    #[test]
+    fn ctrl_left_drags_stay_with_the_camera() {
+        let (mut world, _, origin) = world();
+        let consumed = route(
+            &mut world,
+            vec![
+                WindowEvent::ModifiersChanged(ModifiersState::CTRL),
+                move_to(origin),
+                press(MouseButton::Left),
+                move_to(offset(origin, 20.0)),
+                release(MouseButton::Left),
+            ],
+        );
+        assert_eq!(consumed, vec![false; 5]);
+        assert!(drain::<DragStarted>(&mut world).is_empty());
+        assert!(drain::<Clicked>(&mut world).is_empty());
+    }
+
+    #[test]
+    fn releasing_another_button_keeps_the_press() {
+        let (mut world, point, origin) = world();
+        let consumed = route(
+            &mut world,
+            vec![
+                move_to(origin),
+                press(MouseButton::Left),
+                release(MouseButton::Right),
+            ],
+        );
+        assert_eq!(consumed, vec![false, true, false]);
+        assert_eq!(world.resource::<Pointer>().captured(), Some(point));
+        assert!(drain::<Clicked>(&mut world).is_empty());
+
+        route(
+            &mut world,
+            vec![move_to(offset(origin, 20.0)), release(MouseButton::Left)],
+        );
+        assert_eq!(drain::<DragStarted>(&mut world).len(), 1);
+        assert_eq!(drain::<DragEnded>(&mut world).len(), 1);
+    }
+}
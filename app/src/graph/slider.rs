@@ -0,0 +1,182 @@
+use bevy_ecs::prelude::*;
+use two_dimensional::{
+    primitives::text::{add_text, text_size},
+    View,
+};
+
+use super::{
+    add_screen_rect, decimals, Overlay, OverlayLayer, Parameters, Pointer, Theme, Widget, WidgetInput,
+    WidgetPhase, Widgets,
+};
+
+//slider widget ids are the parameter's name behind this
+const SLIDER_ID: &str = "slider:";
+//the layout, in logical pixels from the top left of the window
+const SLIDER_MARGIN: f32 = 12f32;
+const SLIDER_ROW: f32 = 24f32;
+const SLIDER_WIDTH: f32 = 160f32;
+const TRACK_HEIGHT: f32 = 2f32;
+const HANDLE_SIZE: f32 = 10f32;
+//the size of one pixel of the label font, and the gap between the track and its label
+const SLIDER_PIXEL: f32 = 2f32;
+const LABEL_GAP: f32 = 12f32;
+const PANEL_ALPHA: f32 = 0.85;
+const TRACK_ALPHA: f32 = 0.5;
+
+//where one parameter's slider sits, in physical pixels
+struct SliderLayout {
+    left: f32,
+    right: f32,
+    //the middle of the track
+    y: f32,
+}
+
+impl SliderLayout {
+    fn new(view: &View, row: usize) -> Self {
+        let scale_factor = view.scale_factor as f32;
+        let left = (SLIDER_MARGIN + HANDLE_SIZE / 2f32) * scale_factor;
+        Self {
+            left,
+            right: left + SLIDER_WIDTH * scale_factor,
+            y: (SLIDER_MARGIN + SLIDER_ROW * (row as f32 + 0.5)) * scale_factor,
+        }
+    }
+
+    fn x(&self, t: f32) -> f32 {
+        self.left + t * (self.right - self.left)
+    }
+
+    //how far along the track a screen x is, from 0 to 1
+    fn t(&self, x: f32) -> f32 {
+        ((x - self.left) / (self.right - self.left)).clamp(0f32, 1f32)
+    }
+}
+
+//pressing or dragging a slider moves its parameter to the value under the cursor
+pub fn slider_input(
+    mut events: EventReader<WidgetInput>,
+    view: Res<View>,
+    mut parameters: ResMut<Parameters>,
+) {
+    for event in events.iter() {
+        let name = match (event.phase, event.id.strip_prefix(SLIDER_ID)) {
+            (WidgetPhase::Pressed | WidgetPhase::Dragged, Some(name)) => name,
+            _ => continue,
+        };
+        let (row, parameter) = match parameters.iter().enumerate().find(|(_, (other, _))| *other == name) {
+            Some((row, (_, parameter))) => (row, parameter),
+            None => continue,
+        };
+
+        let t = SliderLayout::new(&view, row).t(event.position.x as f32);
+        let value = parameter.snap(parameter.min + t * (parameter.max - parameter.min));
+        //only touch the parameters when the value moved, so nothing regenerates otherwise
+        if value != parameter.value {
+            parameters.set(name, value);
+        }
+    }
+}
+
+//a slider for every parameter in the top left of the window, drawn with the overlay
+//and registered as widgets so presses on them never reach the graph
+pub fn update_sliders(
+    parameters: Res<Parameters>,
+    pointer: Res<Pointer>,
+    mut held: Local<Option<String>>,
+    view: Res<View>,
+    theme: Res<Theme>,
+    mut widgets: ResMut<Widgets>,
+    mut overlay: ResMut<Overlay>,
+) {
+    //the pointer changes with every cursor move, only grabbing or letting go of a slider matters here
+    let grabbed = pointer.widget() != held.as_deref();
+    if !parameters.is_changed() && !grabbed && !view.is_changed() && !theme.is_changed() {
+        return;
+    }
+    *held = pointer.widget().map(str::to_string);
+
+    let scale_factor = view.scale_factor as f32;
+    let pixel = SLIDER_PIXEL * scale_factor;
+    let half_track = TRACK_HEIGHT * scale_factor / 2f32;
+    let half_handle = HANDLE_SIZE * scale_factor / 2f32;
+    let half_row = SLIDER_ROW * scale_factor / 2f32;
+
+    let labels: Vec<String> = parameters
+        .iter()
+        .map(|(name, parameter)| format!("{} = {:.*}", name, decimals(parameter.step), parameter.value))
+        .collect();
+
+    let mut layer = OverlayLayer::default();
+    let mut regions = Vec::new();
+    if !labels.is_empty() {
+        let label_width = labels
+            .iter()
+            .map(|label| text_size(label, pixel).0)
+            .fold(0f32, f32::max);
+        let first = SliderLayout::new(&view, 0);
+        let last = SliderLayout::new(&view, labels.len() - 1);
+        let [r, g, b] = theme.background;
+        add_screen_rect(
+            &mut layer.lines,
+            (first.left - half_handle, first.y - half_row),
+            (first.right + LABEL_GAP * scale_factor + label_width + half_handle, last.y + half_row),
+            [r, g, b, PANEL_ALPHA],
+        );
+    }
+
+    for (row, ((name, parameter), label)) in parameters.iter().zip(&labels).enumerate() {
+        let layout = SliderLayout::new(&view, row);
+        let id = format!("{}{}", SLIDER_ID, name);
+        let range = parameter.max - parameter.min;
+        let t = if range > 0f32 {
+            ((parameter.value - parameter.min) / range).clamp(0f32, 1f32)
+        } else {
+            0f32
+        };
+        let handle = layout.x(t);
+
+        let [r, g, b] = theme.axis;
+        add_screen_rect(
+            &mut layer.lines,
+            (layout.left, layout.y - half_track),
+            (layout.right, layout.y + half_track),
+            [r, g, b, TRACK_ALPHA],
+        );
+        //the held slider's handle is drawn in the selection color
+        let [r, g, b] = if pointer.widget() == Some(id.as_str()) {
+            theme.selection
+        } else {
+            theme.axis
+        };
+        add_screen_rect(
+            &mut layer.lines,
+            (handle - half_handle, layout.y - half_handle),
+            (handle + half_handle, layout.y + half_handle),
+            [r, g, b, 1f32],
+        );
+
+        let [r, g, b] = theme.label_text;
+        let text_height = text_size(label, pixel).1;
+        add_text(
+            &mut layer.text,
+            label,
+            (layout.right + LABEL_GAP * scale_factor, layout.y - text_height / 2f32),
+            pixel,
+            [r, g, b, 1f32],
+        );
+
+        regions.push(Widget {
+            id,
+            min: (layout.left - half_handle, layout.y - half_row),
+            max: (layout.right + half_handle, layout.y + half_row),
+        });
+    }
+
+    widgets.regions.retain(|widget| !widget.id.starts_with(SLIDER_ID));
+    widgets.regions.extend(regions);
+
+    if layer.is_empty() && overlay.sliders.is_empty() {
+        return;
+    }
+    overlay.sliders = layer;
+}
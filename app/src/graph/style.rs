@@ -0,0 +1,78 @@
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+//how a plotted entity draws its lines, the width is in pixels
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct Stroke {
+    pub color: [f32; 3],
+    pub width: f32,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            color: [1f32, 0f32, 0f32],
+            width: 4f32,
+        }
+    }
+}
+
+//the color of the area a plot encloses, no color means the plot isn't filled
+#[derive(Component, Clone, Default, Serialize, Deserialize)]
+pub struct Fill {
+    pub color: Option<[f32; 3]>,
+}
+
+//hidden plots aren't generated or drawn, but keep their place in the scene
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct Visibility {
+    pub visible: bool,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+//plots are drawn from lowest to highest, ties are drawn in spawn order
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ZOrder(pub i32);
+
+//plots that should take the next color from the palette when they are spawned
+#[derive(Component, Clone, Copy)]
+pub struct AutoColor(pub bool);
+
+//the colors handed out to new plots, in order, wrapping around when they run out
+//filled from the theme, see apply_theme
+pub struct Palette {
+    pub colors: Vec<[f32; 3]>,
+    next: usize,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<[f32; 3]>) -> Self {
+        Self { colors, next: 0 }
+    }
+
+    pub fn next_color(&mut self) -> [f32; 3] {
+        if self.colors.is_empty() {
+            return Stroke::default().color;
+        }
+        let color = self.colors[self.next % self.colors.len()];
+        self.next += 1;
+        color
+    }
+}
+
+//give every newly spawned plot that asked for it the next color in the palette
+pub fn assign_palette_colors(
+    mut palette: ResMut<Palette>,
+    mut query: Query<(&mut Stroke, &AutoColor), Added<AutoColor>>,
+) {
+    for (mut stroke, auto_color) in &mut query {
+        if auto_color.0 {
+            stroke.color = palette.next_color();
+        }
+    }
+}
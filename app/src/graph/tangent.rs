@@ -0,0 +1,53 @@
+use two_dimensional::View;
+
+use super::{EquationBox, Plottable, SceneBuilder};
+use crate::document::{PlotDocument, ShapeKind};
+
+//the line touching an equation at x, drawn across the whole view, with a dot where it touches
+//driven by a point it follows the point's x
+pub struct TangentLine {
+    pub equation: EquationBox,
+    pub x: f32,
+}
+
+impl TangentLine {
+    pub fn new(equation: EquationBox, x: f32) -> Self {
+        Self { equation, x }
+    }
+}
+
+impl Plottable for TangentLine {
+    fn generate(&self, view: &View, out: &mut SceneBuilder) {
+        let y = self.equation.f(self.x);
+        let slope = self.equation.derivative(self.x);
+        //no tangent at poles or corners
+        if !y.is_finite() || !slope.is_finite() {
+            return;
+        }
+
+        let at = |x: f32| (x, y + slope * (x - self.x));
+        out.line(at(view.left), at(view.right));
+        out.point((self.x, y));
+    }
+
+    fn drive(&mut self, points: &[(f32, f32)]) {
+        if let Some(&(x, _)) = points.first() {
+            self.x = x;
+        }
+    }
+
+    fn equation(&self) -> Option<&EquationBox> {
+        Some(&self.equation)
+    }
+
+    fn equation_mut(&mut self) -> Option<&mut EquationBox> {
+        Some(&mut self.equation)
+    }
+
+    fn to_document(&self) -> Option<PlotDocument> {
+        Some(PlotDocument::Shape(ShapeKind::Tangent {
+            equation: self.equation.to_document()?,
+            x: self.x,
+        }))
+    }
+}
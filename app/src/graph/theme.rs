@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::config::{load_config, ConfigError};
+use super::{AutoColor, GraphRenderContext, Palette, Stroke};
+
+//every color the graph draws with that isn't chosen per plot
+//swapping the resource restyles the running app, the systems below pick it up on the next frame
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub background: [f32; 3],
+    pub axis: [f32; 3],
+    pub major_grid: [f32; 3],
+    pub minor_grid: [f32; 3],
+    pub label_text: [f32; 3],
+    pub selection: [f32; 3],
+    //handed out in order to plots that don't pick their own color
+    pub palette: Vec<[f32; 3]>,
+}
+
+//the order ctrl+t cycles through
+const BUILTIN_THEMES: [&str; 3] = ["light", "dark", "high_contrast"];
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            background: [1f32, 1f32, 1f32],
+            axis: [0f32, 0f32, 0f32],
+            major_grid: [0.3, 0.3, 0.3],
+            minor_grid: [0.8, 0.8, 0.8],
+            label_text: [0.1, 0.1, 0.1],
+            selection: [1.0, 0.6, 0.0],
+            palette: vec![
+                [0.84, 0.15, 0.16],
+                [0.12, 0.47, 0.71],
+                [0.17, 0.63, 0.17],
+                [1.0, 0.5, 0.05],
+                [0.58, 0.4, 0.74],
+                [0.55, 0.34, 0.29],
+                [0.89, 0.47, 0.76],
+                [0.09, 0.75, 0.81],
+            ],
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            background: [0.1, 0.1, 0.12],
+            axis: [0.9, 0.9, 0.9],
+            major_grid: [0.45, 0.45, 0.5],
+            minor_grid: [0.22, 0.22, 0.25],
+            label_text: [0.9, 0.9, 0.9],
+            selection: [1.0, 0.8, 0.2],
+            palette: vec![
+                [1.0, 0.42, 0.42],
+                [0.4, 0.7, 1.0],
+                [0.45, 0.85, 0.45],
+                [1.0, 0.7, 0.3],
+                [0.8, 0.6, 1.0],
+                [0.85, 0.65, 0.5],
+                [1.0, 0.6, 0.85],
+                [0.3, 0.9, 0.95],
+            ],
+        }
+    }
+
+    //black on white with the Okabe-Ito palette, which stays distinguishable with every common color blindness
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high_contrast".to_string(),
+            background: [1f32, 1f32, 1f32],
+            axis: [0f32, 0f32, 0f32],
+            major_grid: [0.2, 0.2, 0.2],
+            minor_grid: [0.6, 0.6, 0.6],
+            label_text: [0f32, 0f32, 0f32],
+            selection: [0.0, 0.0, 0.0],
+            palette: vec![
+                [0.9, 0.62, 0.0],
+                [0.34, 0.71, 0.91],
+                [0.0, 0.62, 0.45],
+                [0.94, 0.89, 0.26],
+                [0.0, 0.45, 0.7],
+                [0.84, 0.37, 0.0],
+                [0.8, 0.47, 0.65],
+                [0f32, 0f32, 0f32],
+            ],
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Self::light()),
+            "dark" => Some(Self::dark()),
+            "high_contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    //the built in theme after this one, custom themes go back to the first
+    pub fn next_builtin(&self) -> Self {
+        let next = BUILTIN_THEMES
+            .iter()
+            .position(|name| *name == self.name)
+            .map_or(0, |i| (i + 1) % BUILTIN_THEMES.len());
+        Self::builtin(BUILTIN_THEMES[next]).unwrap()
+    }
+
+    //themes are saved in the same ron format as documents
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        load_config("theme", path)
+    }
+
+    //a built in theme by name, otherwise a theme file
+    pub fn from_name_or_path(name: &str) -> Result<Self, ConfigError> {
+        match Self::builtin(name) {
+            Some(theme) => Ok(theme),
+            None => Self::load(name),
+        }
+    }
+
+    pub fn background_color(&self) -> wgpu::Color {
+        let [r, g, b] = self.background;
+        wgpu::Color {
+            r: r as f64,
+            g: g as f64,
+            b: b as f64,
+            a: 1f64,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+//push a new theme to the renderer and recolor every plot that took its color from the palette
+pub fn apply_theme(
+    theme: Res<Theme>,
+    mut context: ResMut<GraphRenderContext>,
+    mut palette: ResMut<Palette>,
+    mut plots: Query<(Entity, &mut Stroke, &AutoColor)>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+
+    context.background_color = theme.background_color();
+    *palette = Palette::new(theme.palette.clone());
+
+    //hand the colors out in spawn order so a plot keeps its place in the palette across themes
+    let mut auto_colored: Vec<_> = plots
+        .iter_mut()
+        .filter(|(_, _, auto_color)| auto_color.0)
+        .collect();
+    auto_colored.sort_by_key(|(entity, _, _)| *entity);
+    for (_, mut stroke, _) in auto_colored {
+        stroke.color = palette.next_color();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{assign_palette_colors, Circle, PlotBundle};
+
+    const STYLED: [f32; 3] = [0.1, 0.2, 0.3];
+
+    #[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+    struct Assign;
+
+    fn theme_stage() -> SystemStage {
+        SystemStage::single_threaded()
+            .with_system(assign_palette_colors.label(Assign))
+            .with_system(apply_theme.after(Assign))
+    }
+
+    #[test]
+    fn switching_themes_recolors_only_auto_colored_plots() {
+        let mut world = World::new();
+        let theme = Theme::light();
+        world.insert_resource(Palette::new(theme.palette.clone()));
+        world.insert_resource(theme);
+        world.insert_resource(GraphRenderContext {
+            background_color: Theme::light().background_color(),
+        });
+
+        let auto = world
+            .spawn()
+            .insert_bundle(PlotBundle::new(Circle::new((0f32, 0f32), 1f32)))
+            .id();
+        let styled = world
+            .spawn()
+            .insert_bundle(PlotBundle::new(Circle::new((0f32, 0f32), 2f32)).with_stroke(Stroke {
+                color: STYLED,
+                width: 3f32,
+            }))
+            .id();
+
+        let mut stage = theme_stage();
+        stage.run(&mut world);
+        world.clear_trackers();
+        assert_eq!(world.get::<Stroke>(auto).unwrap().color, Theme::light().palette[0]);
+        assert_eq!(world.get::<Stroke>(styled).unwrap().color, STYLED);
+
+        *world.resource_mut::<Theme>() = Theme::dark();
+        stage.run(&mut world);
+        assert_eq!(world.get::<Stroke>(auto).unwrap().color, Theme::dark().palette[0]);
+        let stroke = world.get::<Stroke>(styled).unwrap();
+        assert_eq!(stroke.color, STYLED);
+        assert_eq!(stroke.width, 3f32);
+        assert_eq!(
+            world.resource::<GraphRenderContext>().background_color,
+            Theme::dark().background_color()
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use bevy_ecs::prelude::*;
+use two_dimensional::CameraController;
+
+//frames further apart than this count as this long, so nothing jumps after the app was idle
+const MAX_DELTA: Duration = Duration::from_millis(100);
+
+//how much time the current frame covers, everything animated reads this instead of the clock
+//so it can be driven by a fake clock by calling advance instead of running update_frame_time
+#[derive(Default)]
+pub struct FrameTime {
+    //seconds since the last frame
+    pub delta: f32,
+    //seconds since the first frame
+    pub elapsed: f64,
+    last_frame: Option<Instant>,
+}
+
+impl FrameTime {
+    pub fn advance(&mut self, delta: Duration) {
+        let delta = delta.min(MAX_DELTA);
+        self.delta = delta.as_secs_f32();
+        self.elapsed += delta.as_secs_f64();
+    }
+
+    //the app stopped drawing until the next event, so the frame after it shouldn't count the wait
+    pub fn pause(&mut self) {
+        self.last_frame = None;
+    }
+}
+
+//whether another frame should be drawn straight after this one, set by anything that is animating
+//the app takes it after every frame and otherwise waits for input before drawing again
+#[derive(Default)]
+pub struct Redraw {
+    continuous: bool,
+}
+
+impl Redraw {
+    pub fn request(&mut self) {
+        self.continuous = true;
+    }
+
+    pub fn take(&mut self) -> bool {
+        std::mem::take(&mut self.continuous)
+    }
+}
+
+//the real clock, the first frame has no delta
+pub fn update_frame_time(mut time: ResMut<FrameTime>) {
+    let now = Instant::now();
+    let delta = time
+        .last_frame
+        .map_or(Duration::ZERO, |last_frame| now - last_frame);
+    time.last_frame = Some(now);
+    time.advance(delta);
+}
+
+//move the camera along its flings, eased zooms and flights
+pub fn animate_camera(
+    time: Res<FrameTime>,
+    mut cam_controller: ResMut<CameraController>,
+    mut redraw: ResMut<Redraw>,
+) {
+    //only borrow the camera mutably when there is something to do, so idle frames don't count as changes
+    if cam_controller.is_animating() {
+        cam_controller.tick(time.delta);
+    }
+    if cam_controller.is_animating() {
+        redraw.request();
+    }
+}
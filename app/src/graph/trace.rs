@@ -0,0 +1,329 @@
+use bevy_ecs::prelude::*;
+use two_dimensional::{CameraController, View};
+use winit::event::{ElementState, KeyboardInput, ModifiersState, WindowEvent};
+
+use super::{
+    add_label, add_screen_rect, decimals, format_point, EquationBox, Hover, KeyMap, NavigationAction,
+    Overlay, OverlayLayer, Plot, Theme, Visibility,
+};
+
+//the marker's size, in logical pixels
+const MARKER_SIZE: f32 = 8f32;
+//how close the marker may get to the edge of the window before the camera scrolls, as a fraction of its size
+const FOLLOW_MARGIN: f32 = 0.1;
+
+//a graphing calculator's trace, a marker that walks along one equation
+//while tracing, the keys that pan move the marker instead, left and right along the curve
+//and up and down to the next equation, moving the cursor snaps the marker to the cursor's x
+//plots built on an equation, like tangent lines, trace the curve they're built on
+type TracedPlot = (Entity, &'static Plot, &'static Visibility);
+
+type TracedChanged = Or<(Changed<Plot>, Changed<Visibility>)>;
+
+#[derive(Default)]
+pub struct Trace {
+    //the equation being traced, None when trace mode is off
+    pub target: Option<Entity>,
+    pub x: f32,
+    pub show_derivative: bool,
+    //set when the keys moved the marker, so the camera follows it
+    follow: bool,
+}
+
+impl Trace {
+    pub fn is_active(&self) -> bool {
+        self.target.is_some()
+    }
+}
+
+fn traced_equation<'a>(plots: &'a Query<TracedPlot>, target: Option<Entity>) -> Option<&'a EquationBox> {
+    let (_, plot, visibility) = plots.get(target?).ok()?;
+    plot.equation().filter(|_| visibility.visible)
+}
+
+//the visible equations in spawn order, the order up and down step through them
+fn visible_equations(plots: &Query<TracedPlot>) -> Vec<Entity> {
+    let mut equations: Vec<Entity> = plots
+        .iter()
+        .filter(|(_, plot, visibility)| visibility.visible && plot.equation().is_some())
+        .map(|(entity, _, _)| entity)
+        .collect();
+    equations.sort();
+    equations
+}
+
+//the equation after or before the current one, wrapping around
+fn step_equation(equations: &[Entity], current: Option<Entity>, forward: bool) -> Option<Entity> {
+    let position = current.and_then(|current| equations.iter().position(|entity| *entity == current));
+    let len = equations.len();
+    let next = match (position, forward) {
+        (_, _) if len == 0 => return None,
+        (Some(i), true) => (i + 1) % len,
+        (Some(i), false) => (i + len - 1) % len,
+        (None, _) => 0,
+    };
+    Some(equations[next])
+}
+
+pub fn trace_input(
+    mut events: EventReader<WindowEvent<'static>>,
+    mut modifiers: Local<ModifiersState>,
+    key_map: Res<KeyMap>,
+    hover: Res<Hover>,
+    plots: Query<TracedPlot>,
+    view: Res<View>,
+    mut trace: ResMut<Trace>,
+) {
+    //the traced equation was despawned, hidden or replaced since, so the trace ends and the pan keys
+    //go back to the camera
+    if trace.is_active() && traced_equation(&plots, trace.target).is_none() {
+        trace.target = None;
+    }
+
+    for event in events.iter() {
+        let key = match event {
+            WindowEvent::ModifiersChanged(new_modifiers) => {
+                *modifiers = *new_modifiers;
+                continue;
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if trace.is_active() {
+                    trace.x = view.screen_to_graph(*position).0;
+                }
+                continue;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => *key,
+            _ => continue,
+        };
+        if modifiers.ctrl() || modifiers.alt() || modifiers.logo() {
+            continue;
+        }
+
+        //one pixel at a time, or one step with shift held
+        let step = if modifiers.shift() {
+            key_map.trace_step
+        } else {
+            view.aspect.0
+        };
+        match key_map.action(key) {
+            //start on the equation under the cursor, or the first one
+            Some(NavigationAction::ToggleTrace) if !trace.is_active() => {
+                let equations = visible_equations(&plots);
+                let hovered = hover.pick.filter(|pick| equations.contains(&pick.entity));
+                trace.target = hovered.map(|pick| pick.entity).or_else(|| equations.first().copied());
+                trace.x = hovered.map_or(view.center_x, |pick| pick.point.0);
+            }
+            Some(NavigationAction::ToggleTrace) => trace.target = None,
+            Some(NavigationAction::ToggleDerivative) => trace.show_derivative = !trace.show_derivative,
+            _ if !trace.is_active() => {}
+            Some(NavigationAction::PanLeft) => {
+                trace.x -= step;
+                trace.follow = true;
+            }
+            Some(NavigationAction::PanRight) => {
+                trace.x += step;
+                trace.follow = true;
+            }
+            Some(NavigationAction::PanUp) => {
+                trace.target = step_equation(&visible_equations(&plots), trace.target, true);
+                trace.follow = true;
+            }
+            Some(NavigationAction::PanDown) => {
+                trace.target = step_equation(&visible_equations(&plots), trace.target, false);
+                trace.follow = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+//scroll the camera so a marker moved by the keys stays on screen
+pub fn follow_trace(
+    mut trace: ResMut<Trace>,
+    view: Res<View>,
+    plots: Query<TracedPlot>,
+    mut cam_controller: ResMut<CameraController>,
+) {
+    if !trace.follow {
+        return;
+    }
+    trace.follow = false;
+
+    let x = trace.x;
+    let y = match traced_equation(&plots, trace.target) {
+        Some(equation) => equation.f(x),
+        None => return,
+    };
+
+    let margin_x = (view.right - view.left) * FOLLOW_MARGIN;
+    let margin_y = (view.top - view.bottom) * FOLLOW_MARGIN;
+    let shift = |value: f32, low: f32, high: f32| {
+        if value < low {
+            value - low
+        } else if value > high {
+            value - high
+        } else {
+            0f32
+        }
+    };
+    let dx = shift(x, view.left + margin_x, view.right - margin_x);
+    //undefined points, like poles, leave the height alone
+    let dy = if y.is_finite() {
+        shift(y, view.bottom + margin_y, view.top - margin_y)
+    } else {
+        0f32
+    };
+    if dx != 0f32 || dy != 0f32 {
+        let (pixel_dx, pixel_dy) = view.graph_vector_to_screen((dx, dy));
+        cam_controller.pan(pixel_dx as f32, -pixel_dy as f32);
+    }
+}
+
+//the marker and the label with the traced point, and its slope when asked for
+pub fn update_trace_overlay(
+    trace: Res<Trace>,
+    view: Res<View>,
+    theme: Res<Theme>,
+    plots: Query<TracedPlot>,
+    changed: Query<(), TracedChanged>,
+    mut overlay: ResMut<Overlay>,
+) {
+    let target_changed = trace.target.is_some_and(|target| changed.get(target).is_ok());
+    if !trace.is_changed() && !view.is_changed() && !theme.is_changed() && !target_changed {
+        return;
+    }
+
+    let mut layer = OverlayLayer::default();
+    if let Some(equation) = traced_equation(&plots, trace.target) {
+        let x = trace.x;
+        let y = equation.f(x);
+        let position = if y.is_finite() {
+            view.graph_to_screen((x, y))
+        } else {
+            //undefined points put the label on the x axis
+            view.graph_to_screen((x, 0f32))
+        };
+        let (sx, sy) = (position.x as f32, position.y as f32);
+
+        let mut label = if y.is_finite() {
+            format_point(&view, (x, y))
+        } else {
+            format!("({:.*}, undefined)", decimals(view.aspect.0), x)
+        };
+        if trace.show_derivative {
+            let slope = equation.derivative(x);
+            label = format!("{}  f'(x) = {:.*}", label, decimals(view.aspect.1 / view.aspect.0), slope);
+        }
+
+        if y.is_finite() {
+            let half = MARKER_SIZE * view.scale_factor as f32 / 2f32;
+            let [r, g, b] = theme.selection;
+            add_screen_rect(&mut layer.lines, (sx - half, sy - half), (sx + half, sy + half), [r, g, b, 1f32]);
+        }
+        add_label(&mut layer, &label, (sx, sy), &view, &theme);
+    }
+
+    //nothing was traced last frame either, leave the overlay alone so it isn't re-uploaded
+    if layer.is_empty() && overlay.trace.is_empty() {
+        return;
+    }
+    overlay.trace = layer;
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+    use winit::event::{DeviceId, VirtualKeyCode};
+
+    use super::*;
+    use crate::expression::Expression;
+    use crate::graph::{keyboard_navigation, Crosshair, FitToContent, Formula, PlotBundle, WindowEvents};
+
+    #[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+    struct TraceInput;
+
+    #[allow(deprecated)]
+    fn press(key: VirtualKeyCode) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            //only ever compared against other ids, never handed to the platform
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::empty(),
+            },
+            is_synthetic: false,
+        }
+    }
+
+    //a world tracing x^2 from x = 1
+    fn tracing() -> (World, Entity) {
+        let mut world = World::new();
+        let camera = CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600));
+        world.insert_resource(camera.view());
+        world.insert_resource(camera);
+        world.insert_resource(KeyMap::default());
+        world.insert_resource(Hover::default());
+        world.insert_resource(Crosshair::default());
+        world.insert_resource(WindowEvents::default());
+        world.insert_resource(Events::<FitToContent>::default());
+
+        let formula = Formula::new(Expression::parse("x^2").unwrap());
+        let equation = world
+            .spawn()
+            .insert_bundle(PlotBundle::new(EquationBox::new(formula)))
+            .id();
+        world.insert_resource(Trace {
+            target: Some(equation),
+            x: 1f32,
+            ..Trace::default()
+        });
+        (world, equation)
+    }
+
+    //presses the key and returns how far the camera moved
+    fn press_left(world: &mut World) -> (f32, f32) {
+        let before = world.resource::<CameraController>().center();
+        world.resource_mut::<WindowEvents>().send(press(VirtualKeyCode::Left));
+        SystemStage::single_threaded()
+            .with_system(trace_input.label(TraceInput))
+            .with_system(keyboard_navigation.after(TraceInput))
+            .run(world);
+        let after = world.resource::<CameraController>().center();
+        (after.0 - before.0, after.1 - before.1)
+    }
+
+    #[test]
+    fn the_trace_takes_the_pan_keys() {
+        let (mut world, equation) = tracing();
+        assert_eq!(press_left(&mut world), (0f32, 0f32));
+        let trace = world.resource::<Trace>();
+        assert_eq!(trace.target, Some(equation));
+        assert!(trace.x < 1f32);
+    }
+
+    #[test]
+    fn hiding_the_traced_equation_ends_the_trace() {
+        let (mut world, equation) = tracing();
+        world.get_mut::<Visibility>(equation).unwrap().visible = false;
+        assert!(press_left(&mut world).0 < 0f32);
+        assert_eq!(world.resource::<Trace>().target, None);
+    }
+
+    #[test]
+    fn despawning_the_traced_equation_ends_the_trace() {
+        let (mut world, equation) = tracing();
+        world.despawn(equation);
+        assert!(press_left(&mut world).0 < 0f32);
+        assert_eq!(world.resource::<Trace>().target, None);
+    }
+}
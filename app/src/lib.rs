@@ -1,2 +1,6 @@
 pub mod graph;
+pub mod document;
+pub mod expression;
+pub mod export;
+pub mod schedule;
 pub mod app;
\ No newline at end of file
@@ -0,0 +1,460 @@
+use bevy_ecs::prelude::*;
+use bevy_tasks::{ComputeTaskPool, TaskPool};
+
+use two_dimensional::primitives::line::render_lines;
+
+use crate::expression::Definitions;
+use crate::graph::{
+    animate_camera, animate_parameters, animation_input, apply_theme, apply_zoom_sensitivity,
+    assign_palette_colors, attach_geometry, begin_frame, bind_parameters, camera_input,
+    clear_background, collect_lines, drag_control_points, drive_dependents, fit_to_content,
+    follow_trace, generate_grid_lines, generate_plots, hover_pick, init_graph_render_context,
+    keyboard_navigation, link_formulas, present_frame, register_parameters, render_overlay,
+    resize_surface, route_hover, slider_input, trace_input, track_cursor, update_camera_uniform,
+    update_crosshair, update_frame_time, update_selection_overlay, update_sliders,
+    update_timeline_overlay, update_trace_overlay, update_view, Animations, Clicked, Crosshair,
+    Cursor, DragEnded, DragStarted, Dragged, FitSettings, FitToContent, FrameTime, GridSettings,
+    Hover, HoverEnter, HoverExit, Hovered, KeyMap, Palette, Parameters, PickSettings, Pointer,
+    Redraw, Theme, Trace, WidgetInput, Widgets, WindowEvents,
+};
+
+//inserted by any system that wants the app to close, checked by the event loop after each frame
+pub struct AppExit;
+
+//the app runs the startup schedule once, after the window and render context are in the world,
+//and then the frame schedule once per redraw, the stages of a frame run in this order
+#[derive(StageLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GraphStage {
+    //creates the camera, view, gpu resources and default settings
+    Startup,
+    //consumes the window events the event loop forwarded since the last frame, mouse events go to
+    //the widgets and the entity under the cursor before the camera, then advances the frame clock,
+    //the camera's animations and the parameter animations
+    Input,
+    //turns the camera into the view everything else reads, and gives new plots a geometry cache
+    //and their palette color, restyles everything when the theme changes, links formulas against
+    //the definitions they call and hands parameter values to the formulas that read them,
+    //then fits the view to the plots if that was asked for
+    Update,
+    //regenerates the geometry whose inputs changed, gathers it into the line list, picks what is under
+    //the cursor, rebuilds the interaction overlay and updates the camera uniform,
+    //an idle frame does no work here
+    Extract,
+    //acquires the frame and records every pass into it, each pass is tagged with a RenderPhase
+    //so the systems here can run in any order and still draw background, fills, lines, points,
+    //overlays and text in that order
+    Render,
+    //submits the recorded passes and shows the frame
+    Present,
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum FrameSystem {
+    UpdateEvents,
+    Resize,
+    Trace,
+    CameraInput,
+    DragPoints,
+    FollowTrace,
+    Fit,
+    Generate,
+    Pick,
+    AssignColors,
+    Link,
+    Parameters,
+    Bind,
+    Animate,
+}
+
+pub fn startup_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule.add_stage(
+        GraphStage::Startup,
+        SystemStage::single_threaded()
+            .with_system(init_task_pool)
+            .with_system(init_graph_render_context)
+            .with_system(init_grid_settings)
+            .with_system(init_theme)
+            .with_system(init_key_map)
+            .with_system(init_frame_time)
+            .with_system(init_fit)
+            .with_system(init_interaction)
+            .with_system(init_parameters),
+    );
+    schedule
+}
+
+pub fn frame_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule
+        .add_stage(
+            GraphStage::Input,
+            SystemStage::single_threaded()
+                .with_system(WindowEvents::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<FitToContent>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<Hovered>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<HoverEnter>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<HoverExit>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<Clicked>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<DragStarted>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<Dragged>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<DragEnded>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(Events::<WidgetInput>::update_system.label(FrameSystem::UpdateEvents))
+                .with_system(apply_zoom_sensitivity.before(FrameSystem::Resize))
+                .with_system(track_cursor.after(FrameSystem::UpdateEvents))
+                .with_system(
+                    resize_surface
+                        .label(FrameSystem::Resize)
+                        .after(FrameSystem::UpdateEvents),
+                )
+                //the trace takes the pan keys before keyboard navigation sees them
+                .with_system(
+                    trace_input
+                        .label(FrameSystem::Trace)
+                        .after(FrameSystem::Resize)
+                        .before(FrameSystem::CameraInput),
+                )
+                .with_system(
+                    camera_input
+                        .label(FrameSystem::CameraInput)
+                        .after(FrameSystem::Resize),
+                )
+                .with_system(
+                    keyboard_navigation
+                        .label(FrameSystem::CameraInput)
+                        .after(FrameSystem::Resize),
+                )
+                .with_system(update_frame_time.before(FrameSystem::CameraInput))
+                .with_system(follow_trace.label(FrameSystem::FollowTrace).after(FrameSystem::Trace))
+                .with_system_set(control_point_systems())
+                .with_system(
+                    slider_input
+                        .after(FrameSystem::CameraInput)
+                        .before(FrameSystem::Animate),
+                )
+                .with_system(animation_input.after(FrameSystem::Resize).before(FrameSystem::Animate))
+                //a slider dragged while its parameter plays moves the animation along with it
+                .with_system(
+                    animate_parameters
+                        .label(FrameSystem::Animate)
+                        .after(FrameSystem::CameraInput),
+                )
+                .with_system(
+                    animate_camera
+                        .after(FrameSystem::CameraInput)
+                        .after(FrameSystem::FollowTrace),
+                ),
+        )
+        //a fit asked for by a key or by opening a document waits until the plots are linked and bound,
+        //so formulas calling definitions and reading parameters are fitted as they will be drawn,
+        //the flight it starts moves the camera from the next frame on
+        .add_stage(
+            GraphStage::Update,
+            update_stage().with_system(fit_to_content.label(FrameSystem::Fit).after(FrameSystem::Bind)),
+        )
+        .add_stage(
+            GraphStage::Extract,
+            SystemStage::single_threaded()
+                .with_system(generate_grid_lines.label(FrameSystem::Generate))
+                .with_system(generate_plots.label(FrameSystem::Generate))
+                .with_system(collect_lines.after(FrameSystem::Generate))
+                //picks against the geometry generated for this frame's view
+                .with_system(hover_pick.label(FrameSystem::Pick).after(FrameSystem::Generate))
+                .with_system(route_hover.after(FrameSystem::Pick))
+                .with_system(update_camera_uniform)
+                .with_system(update_selection_overlay)
+                .with_system(update_crosshair)
+                .with_system(update_trace_overlay)
+                .with_system(update_sliders)
+                .with_system(update_timeline_overlay),
+        )
+        .add_stage(
+            GraphStage::Render,
+            SystemStage::single_threaded()
+                .with_system(begin_frame.exclusive_system().at_start())
+                .with_system(clear_background)
+                .with_system(render_lines)
+                .with_system(render_overlay),
+        )
+        .add_stage(
+            GraphStage::Present,
+            SystemStage::single_threaded().with_system(present_frame.exclusive_system()),
+        );
+    schedule
+}
+
+//shared by the frame and export schedules
+fn update_stage() -> SystemStage {
+    SystemStage::single_threaded()
+        .with_system(update_view)
+        .with_system(attach_geometry)
+        .with_system(assign_palette_colors.label(FrameSystem::AssignColors))
+        //a new theme recolors every auto colored plot, including the ones spawned this frame
+        .with_system(apply_theme.after(FrameSystem::AssignColors))
+        //formulas are linked against the definitions they call first, since those can read parameters too
+        .with_system(link_formulas.label(FrameSystem::Link))
+        //formulas pick up the parameters they read, new ones start with the defaults
+        .with_system(register_parameters.label(FrameSystem::Parameters).after(FrameSystem::Link))
+        .with_system(bind_parameters.label(FrameSystem::Bind).after(FrameSystem::Parameters))
+}
+
+//the plots driven by a dragged point regenerate in the same frame as the drag
+fn control_point_systems() -> SystemSet {
+    SystemSet::new()
+        .with_system(
+            drag_control_points
+                .label(FrameSystem::DragPoints)
+                .after(FrameSystem::CameraInput),
+        )
+        .with_system(drive_dependents.after(FrameSystem::DragPoints))
+}
+
+//draws one frame of an export, see export_animation, which inserts the frame to draw into before
+//each run and submits it after, there is no input stage and no overlay so only the scene is drawn
+pub fn export_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule
+        .add_stage(GraphStage::Update, update_stage())
+        .add_stage(
+            GraphStage::Extract,
+            SystemStage::single_threaded()
+                .with_system(generate_grid_lines.label(FrameSystem::Generate))
+                .with_system(generate_plots.label(FrameSystem::Generate))
+                .with_system(collect_lines.after(FrameSystem::Generate))
+                .with_system(update_camera_uniform),
+        )
+        .add_stage(
+            GraphStage::Render,
+            SystemStage::single_threaded()
+                .with_system(clear_background)
+                .with_system(render_lines),
+        );
+    schedule
+}
+
+//generate_plots spreads plots across this, one thread per core
+fn init_task_pool() {
+    ComputeTaskPool::init(TaskPool::default);
+}
+
+//documents loaded after startup replace these
+fn init_grid_settings(mut commands: Commands) {
+    commands.insert_resource(GridSettings::default());
+}
+
+//the clock, and whether the app keeps drawing or waits for input, see Redraw
+fn init_frame_time(mut commands: Commands) {
+    commands.insert_resource(FrameTime::default());
+    commands.insert_resource(Redraw::default());
+}
+
+//fit requests are sent by the keyboard and by the app when it opens a document without a camera
+fn init_fit(mut commands: Commands) {
+    commands.insert_resource(FitSettings::default());
+    commands.insert_resource(Events::<FitToContent>::default());
+}
+
+//the state behind the cursor, the crosshair, picking, the pointer events and the trace
+fn init_interaction(mut commands: Commands) {
+    commands.insert_resource(Cursor::default());
+    commands.insert_resource(Pointer::default());
+    commands.insert_resource(Events::<HoverEnter>::default());
+    commands.insert_resource(Events::<HoverExit>::default());
+    commands.insert_resource(Events::<Clicked>::default());
+    commands.insert_resource(Events::<DragStarted>::default());
+    commands.insert_resource(Events::<Dragged>::default());
+    commands.insert_resource(Events::<DragEnded>::default());
+    commands.insert_resource(Crosshair::default());
+    commands.insert_resource(PickSettings::default());
+    commands.insert_resource(Hover::default());
+    commands.insert_resource(Events::<Hovered>::default());
+    commands.insert_resource(Trace::default());
+    commands.insert_resource(Widgets::default());
+    commands.insert_resource(Events::<WidgetInput>::default());
+}
+
+//documents bring their own parameters and definitions, formulas add the parameters they're missing
+fn init_parameters(mut commands: Commands) {
+    commands.insert_resource(Parameters::default());
+    commands.insert_resource(Definitions::default());
+    commands.insert_resource(Animations::default());
+}
+
+//replaced by --keymap
+fn init_key_map(mut commands: Commands) {
+    commands.insert_resource(KeyMap::default());
+}
+
+fn init_theme(mut commands: Commands) {
+    let theme = Theme::default();
+    commands.insert_resource(Palette::new(theme.palette.clone()));
+    commands.insert_resource(theme);
+}
+
+#[cfg(test)]
+mod tests {
+    use two_dimensional::primitives::line::LineList;
+    use two_dimensional::{CameraController, View};
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::expression::Expression;
+    use crate::graph::{
+        Circle, ControlPointBundle, DrivenBy, EquationBox, Formula, Geometry, GridGeometry, PlotBundle,
+    };
+
+    //the systems of a frame between a drag and the regenerated geometry, the rest need a gpu
+    fn drag_schedule() -> Schedule {
+        let mut schedule = Schedule::default();
+        schedule
+            .add_stage(
+                GraphStage::Input,
+                SystemStage::single_threaded()
+                    .with_system(Events::<Dragged>::update_system.label(FrameSystem::UpdateEvents))
+                    .with_system_set(control_point_systems()),
+            )
+            .add_stage(
+                GraphStage::Update,
+                SystemStage::single_threaded().with_system(attach_geometry),
+            )
+            .add_stage(
+                GraphStage::Extract,
+                SystemStage::single_threaded().with_system(generate_plots),
+            );
+        schedule
+    }
+
+    fn center(geometry: &Geometry) -> (f32, f32) {
+        let (mut left, mut right) = (f32::INFINITY, f32::NEG_INFINITY);
+        let (mut bottom, mut top) = (f32::INFINITY, f32::NEG_INFINITY);
+        for &(x, y) in geometry.shapes.segments.iter().flat_map(|(a, b)| [a, b]) {
+            left = left.min(x);
+            right = right.max(x);
+            bottom = bottom.min(y);
+            top = top.max(y);
+        }
+        ((left + right) / 2f32, (bottom + top) / 2f32)
+    }
+
+    #[test]
+    fn dragged_points_regenerate_their_dependents_the_same_frame() {
+        init_task_pool();
+        let mut world = World::new();
+        world.insert_resource(Events::<Dragged>::default());
+        let camera = CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600));
+        world.insert_resource(View::from(&camera));
+        world.insert_resource(camera);
+
+        let point = world
+            .spawn()
+            .insert_bundle(ControlPointBundle::new((0f32, 0f32)))
+            .id();
+        let circle = world
+            .spawn()
+            .insert_bundle(PlotBundle::new(Circle::new((0f32, 0f32), 1f32)))
+            .insert(DrivenBy(vec![point]))
+            .id();
+
+        let mut schedule = drag_schedule();
+        schedule.run(&mut world);
+        world.clear_trackers();
+        let (x, y) = center(world.get::<Geometry>(circle).unwrap());
+        assert!(x.abs() < 1e-3 && y.abs() < 1e-3, "drawn around ({}, {})", x, y);
+
+        world.resource_mut::<Events<Dragged>>().send(Dragged {
+            entity: point,
+            point: (2f32, 3f32),
+            delta: (2f32, 3f32),
+        });
+        schedule.run(&mut world);
+
+        assert_eq!(world.get::<Geometry>(point).unwrap().shapes.points, vec![(2f32, 3f32)]);
+        let (x, y) = center(world.get::<Geometry>(circle).unwrap());
+        assert!((x - 2f32).abs() < 1e-3 && (y - 3f32).abs() < 1e-3, "drawn around ({}, {})", x, y);
+    }
+
+    #[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+    struct Collect;
+
+    //what the extract stage touched in the frame that just ran
+    #[derive(Default)]
+    struct Rebuilt {
+        geometry: usize,
+        lines: bool,
+    }
+
+    fn record_rebuilt(
+        geometry: Query<(), Changed<Geometry>>,
+        lines: Res<LineList>,
+        mut rebuilt: ResMut<Rebuilt>,
+    ) {
+        *rebuilt = Rebuilt {
+            geometry: geometry.iter().count(),
+            lines: lines.is_changed(),
+        };
+    }
+
+    //the update and extract stages without the systems that need a gpu
+    fn idle_schedule() -> Schedule {
+        let mut schedule = Schedule::default();
+        schedule
+            .add_stage(
+                GraphStage::Update,
+                SystemStage::single_threaded()
+                    .with_system(update_view)
+                    .with_system(attach_geometry)
+                    .with_system(assign_palette_colors)
+                    .with_system(link_formulas.label(FrameSystem::Link))
+                    .with_system(register_parameters.label(FrameSystem::Parameters).after(FrameSystem::Link))
+                    .with_system(bind_parameters.after(FrameSystem::Parameters)),
+            )
+            .add_stage(
+                GraphStage::Extract,
+                SystemStage::single_threaded()
+                    .with_system(generate_grid_lines.label(FrameSystem::Generate))
+                    .with_system(generate_plots.label(FrameSystem::Generate))
+                    .with_system(collect_lines.label(Collect).after(FrameSystem::Generate))
+                    .with_system(record_rebuilt.after(Collect)),
+            );
+        schedule
+    }
+
+    #[test]
+    fn idle_frames_rebuild_nothing() {
+        init_task_pool();
+        let mut world = World::new();
+        let camera = CameraController::new(0f32, 0f32, PhysicalSize::new(800, 600));
+        world.insert_resource(View::from(&camera));
+        world.insert_resource(camera);
+        let theme = Theme::default();
+        world.insert_resource(Palette::new(theme.palette.clone()));
+        world.insert_resource(theme);
+        world.insert_resource(GridSettings::default());
+        world.insert_resource(GridGeometry::default());
+        world.insert_resource(LineList::new());
+        world.insert_resource(Parameters::default());
+        world.insert_resource(Definitions::default());
+        world.insert_resource(Rebuilt::default());
+
+        let formula = Formula::new(Expression::parse("a*x^2").unwrap());
+        world.spawn().insert_bundle(PlotBundle::new(EquationBox::new(formula)));
+        world
+            .spawn()
+            .insert_bundle(PlotBundle::new(Circle::new((1f32, 2f32), 3f32)));
+
+        let mut schedule = idle_schedule();
+        schedule.run(&mut world);
+        world.clear_trackers();
+        let rebuilt = world.resource::<Rebuilt>();
+        assert_eq!(rebuilt.geometry, 2);
+        assert!(rebuilt.lines);
+        let vertices = world.resource::<LineList>().vertices().len();
+        assert!(vertices > 0);
+
+        schedule.run(&mut world);
+        let rebuilt = world.resource::<Rebuilt>();
+        assert_eq!(rebuilt.geometry, 0, "an idle frame regenerated plots");
+        assert!(!rebuilt.lines, "an idle frame rebuilt the line list");
+        assert_eq!(world.resource::<LineList>().vertices().len(), vertices);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{fmt, num::NonZeroU32, sync::mpsc};
+
+use crate::{Frame, RenderContext};
+
+#[derive(Debug)]
+pub enum CaptureError {
+    //the surface format has no 8 bit rgba or bgra layout to read back
+    UnsupportedFormat(wgpu::TextureFormat),
+    Map(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => write!(f, "can't read back frames in {:?}", format),
+            Self::Map(e) => write!(f, "couldn't read back the frame: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+//an offscreen texture frames can be drawn into and read back from, for exporting pictures
+//it has the surface's format, so the pipelines made for the window draw into it unchanged
+pub struct Capture {
+    texture: wgpu::Texture,
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    //copies need every row of the buffer padded out to an alignment
+    padded_row: u32,
+    //bgra surfaces are swapped to rgba when reading back
+    swap_red_blue: bool,
+}
+
+impl Capture {
+    pub fn new(render_context: &RenderContext, width: u32, height: u32) -> Result<Self, CaptureError> {
+        let format = render_context.config.format;
+        let swap_red_blue = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            _ => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+
+        let texture = render_context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = (width * 4).div_ceil(align) * align;
+        let buffer = render_context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (padded_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            texture,
+            buffer,
+            width,
+            height,
+            padded_row,
+            swap_red_blue,
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    //a frame that draws into the texture, present it before reading back
+    pub fn frame(&self) -> Frame {
+        Frame::offscreen(self.texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    //what was last drawn, as tightly packed rgba rows from the top of the picture down
+    //this waits for the gpu to finish everything submitted so far
+    pub fn read(&self, render_context: &RenderContext) -> Result<Vec<u8>, CaptureError> {
+        let mut encoder = render_context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Command Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(self.padded_row),
+                    rows_per_image: NonZeroU32::new(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        render_context.queue.submit(Some(encoder.finish()));
+
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            //the receiver is waiting below, it can't have gone away
+            let _ = sender.send(result);
+        });
+        render_context.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("the buffer is always mapped after waiting on the device")
+            .map_err(CaptureError::Map)?;
+
+        let row = (self.width * 4) as usize;
+        let mut pixels = Vec::with_capacity(row * self.height as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for padded in mapped.chunks(self.padded_row as usize) {
+                pixels.extend_from_slice(&padded[..row]);
+            }
+        }
+        self.buffer.unmap();
+
+        if self.swap_red_blue {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(pixels)
+    }
+}
@@ -0,0 +1,102 @@
+use crate::RenderContext;
+
+//the order passes are drawn in, no matter which order the systems recording them ran in
+//passes in the same phase keep the order they were recorded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderPhase {
+    //clears the frame, always first so nothing drawn before it is lost
+    Background,
+    //filled areas, under every line
+    Fill,
+    Line,
+    Point,
+    //cursor decorations and anything else drawn on top of the scene
+    Overlay,
+    //labels, over the overlays so boxes and lines never hide them
+    Text,
+}
+
+//everything needed to draw a single frame, acquired at the start of rendering
+//passes record into their own command buffers, and present submits them sorted by phase
+pub struct Frame {
+    //None when drawing offscreen, see Capture
+    pub surface_texture: Option<wgpu::SurfaceTexture>,
+    pub view: wgpu::TextureView,
+    passes: Vec<(RenderPhase, wgpu::CommandBuffer)>,
+}
+
+impl Frame {
+    pub fn new(render_context: &RenderContext) -> Result<Self, wgpu::SurfaceError> {
+        let surface_texture = render_context.surface.get_current_texture()?;
+        let view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        Ok(Self {
+            surface_texture: Some(surface_texture),
+            view,
+            passes: Vec::new(),
+        })
+    }
+
+    //a frame drawn into a texture instead of the window
+    pub fn offscreen(view: wgpu::TextureView) -> Self {
+        Self {
+            surface_texture: None,
+            view,
+            passes: Vec::new(),
+        }
+    }
+
+    pub fn add_pass(&mut self, phase: RenderPhase, command_buffer: wgpu::CommandBuffer) {
+        self.passes.push((phase, command_buffer));
+    }
+
+    //the attachment every pass after the background draws onto, keeping what is already there
+    pub fn color_attachment(&self) -> wgpu::RenderPassColorAttachment<'_> {
+        wgpu::RenderPassColorAttachment {
+            view: &self.view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            },
+        }
+    }
+
+    //record the background pass, which clears the whole frame to a color
+    pub fn clear(&mut self, render_context: &RenderContext, color: wgpu::Color) {
+        let mut encoder =
+            render_context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Clear Command Encoder"),
+                });
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        self.add_pass(RenderPhase::Background, encoder.finish());
+    }
+
+    pub fn present(mut self, render_context: &RenderContext) {
+        //a stable sort, so passes within a phase stay in recording order
+        self.passes.sort_by_key(|(phase, _)| *phase);
+        render_context
+            .queue
+            .submit(self.passes.into_iter().map(|(_, command_buffer)| command_buffer));
+        if let Some(surface_texture) = self.surface_texture {
+            surface_texture.present();
+        }
+    }
+}
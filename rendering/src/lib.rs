@@ -1,5 +1,7 @@
 mod render_context;
-mod renderer;
+mod frame;
+mod capture;
 
 pub use render_context::RenderContext;
-pub use renderer::Renderer;
\ No newline at end of file
+pub use frame::{Frame, RenderPhase};
+pub use capture::{Capture, CaptureError};
\ No newline at end of file
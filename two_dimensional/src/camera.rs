@@ -1,12 +1,14 @@
+use std::collections::HashMap;
+
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event::{
-        ElementState, MouseButton, MouseScrollDelta,
+        ElementState, ModifiersState, MouseButton, MouseScrollDelta, Touch, TouchPhase,
         WindowEvent::{self, CursorMoved, MouseInput, MouseWheel},
     },
 };
 
-use super::view::View;
+use super::view::{Rect, View};
 
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
@@ -16,12 +18,64 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
-//TODO: Zooming in always zooms in on the center, make it move the camera towards where your zooming and
+//pixels per graph unit a new camera starts with, and goes back to when reset
+const DEFAULT_SCALE: f32 = 2.0;
+
+//how far each kind of zoom input zooms, in powers of two, so 1 doubles or halves the zoom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomSensitivity {
+    //per pixel of a touchpad scroll
+    pub pixel: f32,
+    //per line of a notched mouse wheel
+    pub line: f32,
+    //per doubling of the distance between two fingers on a touch screen
+    pub pinch: f32,
+}
+
+impl Default for ZoomSensitivity {
+    fn default() -> Self {
+        Self {
+            pixel: 0.001,
+            line: 0.2,
+            pinch: 1.0,
+        }
+    }
+}
+
+//drags smaller than this many pixels across are treated as clicks and don't box zoom
+const MIN_BOX_SIZE: f64 = 4.0;
+//how many views undo zoom can step back through
+const MAX_ZOOM_HISTORY: usize = 64;
+
+//how quickly a flung pan slows down, the speed is multiplied by e^-PAN_FRICTION every second
+const PAN_FRICTION: f32 = 5.0;
+//flings slower than this many pixels per second stop
+const MIN_PAN_SPEED: f32 = 10.0;
+//how quickly scroll zoom catches up with the wheel, the same way as the friction
+const ZOOM_EASE_RATE: f32 = 15.0;
+//how long fits, resets and undos take to fly to their view, in seconds
+const FLY_DURATION: f32 = 0.3;
+
+//an animated move from one center and scale to another
+#[derive(Clone)]
+struct Flight {
+    from_center: (f32, f32),
+    to_center: (f32, f32),
+    from_scale: (f32, f32),
+    to_scale: (f32, f32),
+    elapsed: f32,
+    duration: f32,
+}
+
 #[derive(Clone)]
 pub struct CameraController {
-    //used internally for converting screen to graph
-    scale: f32,
+    //used internally for converting screen to graph, pixels per graph unit along each axis
+    scale_x: f32,
+    scale_y: f32,
+    //keeps both axes at the same scale, so circles stay round
+    lock_aspect: bool,
     resolution: PhysicalSize<u32>,
+    scale_factor: f64,
     //bounds in graph space used to make view
     center_x: f32,
     center_y: f32,
@@ -36,14 +90,30 @@ pub struct CameraController {
     start_press: PhysicalPosition<f64>, //start press in screen space
     start_position: (f32, f32), //the center when we started pressing
     cursor_pos: PhysicalPosition<f64>, //cursor position passed around in events
+    zoom_sensitivity: ZoomSensitivity,
+    touches: HashMap<u64, PhysicalPosition<f64>>, //fingers currently on a touch screen, by id
+    modifiers: ModifiersState, //shift and alt scroll a single axis
+    box_start: Option<PhysicalPosition<f64>>, //where a box zoom started in screen space, while one is being dragged
+    zoom_history: Vec<((f32, f32), (f32, f32))>, //the center and scale before each jump, for undo zoom
+
+    //animation, advanced by tick
+    drag_pixels: (f32, f32), //how far the drag panned since the last tick
+    velocity: (f32, f32), //pan speed in pixels per second, kept after a drag ends so the view glides
+    zoom_remaining: (f32, f32), //scroll zoom still to be applied, in powers of two
+    zoom_target: (f32, f32), //the scale the eased zoom ends at, so it lands there exactly
+    zoom_anchor: PhysicalPosition<f64>, //the point the remaining zoom zooms toward
+    flight: Option<Flight>,
 }
 
 impl CameraController {
     pub fn new(center_x: f32, center_y: f32, resolution: PhysicalSize<u32>) -> Self {
         //generate a default scale from the aspect, assuming each unit is 10px
         let mut instance = Self {
-            scale: 2.0,
+            scale_x: DEFAULT_SCALE,
+            scale_y: DEFAULT_SCALE,
+            lock_aspect: false,
             resolution,
+            scale_factor: 1f64,
 
             center_x,
             center_y,
@@ -57,6 +127,18 @@ impl CameraController {
             start_press: (-1f32, -1f32).into(),
             start_position: (-1f32, -1f32),
             cursor_pos: (-1f32, -1f32).into(),
+            zoom_sensitivity: ZoomSensitivity::default(),
+            touches: HashMap::new(),
+            modifiers: ModifiersState::empty(),
+            box_start: None,
+            zoom_history: Vec::new(),
+
+            drag_pixels: (0f32, 0f32),
+            velocity: (0f32, 0f32),
+            zoom_remaining: (0f32, 0f32),
+            zoom_target: (DEFAULT_SCALE, DEFAULT_SCALE),
+            zoom_anchor: (-1f32, -1f32).into(),
+            flight: None,
         };
         instance.update(); //finish construction here
         instance
@@ -67,15 +149,296 @@ impl CameraController {
         self.update();
     }
 
+    //physical pixels per logical pixel, from the window
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    //the view as it is right now, for converting between the screen and graph space
+    pub fn view(&self) -> View {
+        View::from(self)
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (self.center_x, self.center_y)
+    }
+
+    //pixels per graph unit along x and y
+    pub fn scale(&self) -> (f32, f32) {
+        (self.scale_x, self.scale_y)
+    }
+
+    pub fn lock_aspect(&self) -> bool {
+        self.lock_aspect
+    }
+
+    //locking snaps the y scale to the x scale, and from then on every zoom changes both
+    pub fn set_lock_aspect(&mut self, lock_aspect: bool) {
+        self.lock_aspect = lock_aspect;
+        if lock_aspect {
+            self.scale_y = self.scale_x;
+            self.update();
+        }
+    }
+
+    //jump straight to a center and scale, used when restoring a saved view
+    //with the aspect locked only the x scale is used, any animation is stopped
+    pub fn look_at(&mut self, center_x: f32, center_y: f32, scale: (f32, f32)) {
+        self.stop_animation();
+        self.set_center_and_scale((center_x, center_y), scale);
+    }
+
+    fn set_center_and_scale(&mut self, (center_x, center_y): (f32, f32), (scale_x, scale_y): (f32, f32)) {
+        self.center_x = center_x;
+        self.center_y = center_y;
+        self.scale_x = scale_x;
+        self.scale_y = if self.lock_aspect { scale_x } else { scale_y };
+        self.update();
+    }
+
+    pub fn set_zoom_sensitivity(&mut self, zoom_sensitivity: ZoomSensitivity) {
+        self.zoom_sensitivity = zoom_sensitivity;
+    }
+
+    //move the view by a distance in pixels, positive y moves the view up
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let (graph_dx, graph_dy) = self.view().screen_vector_to_graph((dx as f64, -dy as f64));
+        self.center_x += graph_dx;
+        self.center_y += graph_dy;
+        self.update();
+    }
+
+    //zoom in on the middle of the screen, each step of dzoom halves the scale
+    //the zoom is eased in over the next few ticks
+    pub fn zoom_center(&mut self, dzoom: f32) {
+        let center = PhysicalPosition::new(
+            self.resolution.width as f64 / 2.0,
+            self.resolution.height as f64 / 2.0,
+        );
+        self.ease_zoom(center, dzoom, dzoom);
+    }
+
+    //back to the origin at the default scale
+    pub fn reset(&mut self) {
+        self.push_zoom_history();
+        self.fly_to_center((0f32, 0f32), (DEFAULT_SCALE, DEFAULT_SCALE));
+    }
+
+    //go back to the view before the last reset, fit or box zoom, returns false if there is none
+    pub fn undo_zoom(&mut self) -> bool {
+        match self.zoom_history.pop() {
+            Some((center, scale)) => {
+                self.fly_to_center(center, scale);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //where the camera is headed, the end of the flight if it is flying
+    fn destination(&self) -> ((f32, f32), (f32, f32)) {
+        match &self.flight {
+            Some(flight) => (flight.to_center, flight.to_scale),
+            None => (self.center(), self.scale()),
+        }
+    }
+
+    fn push_zoom_history(&mut self) {
+        if self.zoom_history.len() == MAX_ZOOM_HISTORY {
+            self.zoom_history.remove(0);
+        }
+        let destination = self.destination();
+        self.zoom_history.push(destination);
+    }
+
+    //animate to the center and scale of a view, ticks carry the camera there over a short flight
+    pub fn fly_to(&mut self, view: &View) {
+        self.fly_to_center((view.center_x, view.center_y), view.scale);
+    }
+
+    fn fly_to_center(&mut self, to_center: (f32, f32), to_scale: (f32, f32)) {
+        self.stop_animation();
+        let to_scale = if self.lock_aspect {
+            (to_scale.0, to_scale.0)
+        } else {
+            to_scale
+        };
+        self.flight = Some(Flight {
+            from_center: self.center(),
+            to_center,
+            from_scale: self.scale(),
+            to_scale,
+            elapsed: 0f32,
+            duration: FLY_DURATION,
+        });
+    }
+
+    fn stop_animation(&mut self) {
+        self.velocity = (0f32, 0f32);
+        self.zoom_remaining = (0f32, 0f32);
+        self.flight = None;
+    }
+
+    //true while a fling, an eased zoom or a flight is still moving the camera, or a drag moved since
+    //the last tick, a held button alone isn't, the speed measured while it's held drops to 0 a tick
+    //after the cursor stops so letting go then doesn't fling the view
+    pub fn is_animating(&self) -> bool {
+        self.drag_pixels != (0f32, 0f32)
+            || self.velocity != (0f32, 0f32)
+            || self.zoom_remaining != (0f32, 0f32)
+            || self.flight.is_some()
+    }
+
+    //advance every animation by dt seconds, everything is scaled by dt so the motion
+    //doesn't depend on the frame rate, returns true if the view moved
+    pub fn tick(&mut self, dt: f32) -> bool {
+        if dt <= 0f32 || !self.is_animating() {
+            return false;
+        }
+        let before = (self.center(), self.scale());
+
+        //the pan speed is measured from how far the last tick's worth of dragging went
+        if self.pressed || self.drag_pixels != (0f32, 0f32) {
+            self.velocity = (self.drag_pixels.0 / dt, self.drag_pixels.1 / dt);
+            self.drag_pixels = (0f32, 0f32);
+        }
+        if !self.pressed && self.velocity != (0f32, 0f32) {
+            //the distance covered while the speed decays over the whole of dt,
+            //so one long tick glides as far as several short ones
+            let friction = (-PAN_FRICTION * dt).exp();
+            let glide = (1f32 - friction) / PAN_FRICTION;
+            self.pan(self.velocity.0 * glide, self.velocity.1 * glide);
+            self.velocity = (self.velocity.0 * friction, self.velocity.1 * friction);
+            if self.velocity.0.hypot(self.velocity.1) < MIN_PAN_SPEED {
+                self.velocity = (0f32, 0f32);
+            }
+        }
+
+        if self.zoom_remaining != (0f32, 0f32) {
+            //take a fixed fraction of what's left every second, and finish once what's left can't be seen
+            let fraction = 1f32 - (-ZOOM_EASE_RATE * dt).exp();
+            let (remaining_x, remaining_y) = self.zoom_remaining;
+            if remaining_x.abs().max(remaining_y.abs()) < 0.001 {
+                self.zoom_to(self.zoom_anchor, self.zoom_target);
+                self.zoom_remaining = (0f32, 0f32);
+            } else {
+                let (step_x, step_y) = (remaining_x * fraction, remaining_y * fraction);
+                self.zoom(self.zoom_anchor, step_x, step_y);
+                self.zoom_remaining = (remaining_x - step_x, remaining_y - step_y);
+            }
+        }
+
+        if let Some(mut flight) = self.flight.take() {
+            flight.elapsed += dt;
+            let t = (flight.elapsed / flight.duration).min(1f32);
+            //ease in and out
+            let t = t * t * (3f32 - 2f32 * t);
+            let lerp = |from: f32, to: f32| from + (to - from) * t;
+            //scale is interpolated in log space so zooming in and out feel the same
+            let lerp_scale = |from: f32, to: f32| (from.ln() + (to.ln() - from.ln()) * t).exp();
+            self.set_center_and_scale(
+                (
+                    lerp(flight.from_center.0, flight.to_center.0),
+                    lerp(flight.from_center.1, flight.to_center.1),
+                ),
+                (
+                    lerp_scale(flight.from_scale.0, flight.to_scale.0),
+                    lerp_scale(flight.from_scale.1, flight.to_scale.1),
+                ),
+            );
+            if flight.elapsed < flight.duration {
+                self.flight = Some(flight);
+            } else {
+                //the interpolation can be off by a rounding error at the end, land right on the target
+                self.set_center_and_scale(flight.to_center, flight.to_scale);
+            }
+        }
+
+        before != (self.center(), self.scale())
+    }
+
+    //the rectangle being dragged out for a box zoom, in graph space
+    pub fn selection(&self) -> Option<Rect> {
+        let start = self.box_start?;
+        Some(self.view().screen_rect_to_graph(start, self.cursor_pos))
+    }
+
+    //zoom to exactly the dragged rectangle, unless it was too small to mean anything
+    fn finish_box_zoom(&mut self) {
+        let start = match self.box_start.take() {
+            Some(start) => start,
+            None => return,
+        };
+        let width = (self.cursor_pos.x - start.x).abs();
+        let height = (self.cursor_pos.y - start.y).abs();
+        if width < MIN_BOX_SIZE || height < MIN_BOX_SIZE {
+            return;
+        }
+
+        let selection = self.view().screen_rect_to_graph(start, self.cursor_pos);
+        self.fit(selection);
+    }
+
+    //center on a rectangle in graph space and zoom so all of it is on screen,
+    //stretching each axis to fill the screen unless the aspect is locked
+    pub fn fit(&mut self, rect: Rect) {
+        self.push_zoom_history();
+        let width = rect.width();
+        let height = rect.height();
+        let scale_x = self.resolution.width as f32 / width;
+        let scale_y = self.resolution.height as f32 / height;
+        //a rectangle with no width or height can't set the zoom in that direction
+        let fits_x = width > 0f32 && scale_x.is_finite();
+        let fits_y = height > 0f32 && scale_y.is_finite();
+        let scale = match (fits_x, fits_y) {
+            (true, true) if self.lock_aspect => (scale_x.min(scale_y), scale_x.min(scale_y)),
+            (true, true) => (scale_x, scale_y),
+            (true, false) => (scale_x, if self.lock_aspect { scale_x } else { self.scale_y }),
+            (false, true) => (if self.lock_aspect { scale_y } else { self.scale_x }, scale_y),
+            (false, false) => (self.scale_x, self.scale_y),
+        };
+        self.fly_to_center(rect.center(), scale);
+    }
+
     //return true if the view changed
+    //wheels and touchpad scrolls zoom toward the cursor, pinches only come from touch screens
+    //since winit 0.27 doesn't report trackpad pinches
     pub fn event(&mut self, event: &WindowEvent) -> bool {
         match event {
+            //right drag or ctrl and left drag draw a box to zoom to
+            MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } => {
+                self.box_start = Some(self.cursor_pos);
+                true
+            }
+            MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.modifiers.ctrl() => {
+                self.box_start = Some(self.cursor_pos);
+                true
+            }
+            MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Right | MouseButton::Left,
+                ..
+            } if self.box_start.is_some() => {
+                self.finish_box_zoom();
+                self.pressed = false;
+                true
+            }
             //toggle pressed
             MouseInput {
                 state: ElementState::Pressed,
                 button: MouseButton::Left,
                 ..
             } => {
+                //grabbing the view stops it wherever it is
+                self.stop_animation();
                 self.pressed = true;
                 self.start_press = self.cursor_pos; //set start press in here
                 self.start_position = (self.center_x, self.center_y);
@@ -90,62 +453,162 @@ impl CameraController {
                 false
             }
             CursorMoved { position, .. } => {
-                self.cursor_pos = *position;
                 if self.pressed {
+                    //in the same direction pan moves the view
+                    self.drag_pixels.0 -= (position.x - self.cursor_pos.x) as f32;
+                    self.drag_pixels.1 += (position.y - self.cursor_pos.y) as f32;
+                }
+                self.cursor_pos = *position;
+                if self.box_start.is_some() {
+                    //the selection follows the cursor
+                    true
+                } else if self.pressed {
                     //convert position to graph space
-                    self.center_x = self.start_position.0
-                        - (position.x - self.start_press.x) as f32 / self.scale;
-                    self.center_y = self.start_position.1
-                        + (position.y - self.start_press.y) as f32 / self.scale;
+                    let (dx, dy) = self.view().screen_vector_to_graph((
+                        position.x - self.start_press.x,
+                        position.y - self.start_press.y,
+                    ));
+                    self.center_x = self.start_position.0 - dx;
+                    self.center_y = self.start_position.1 - dy;
                     self.update();
                     true
                 } else {
                     false
                 }
             }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                false
+            }
+            //every kind of scroll zooms toward the cursor, scrolling up zooms in
             MouseWheel {
                 delta: MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }),
                 ..
             } => {
                 //use the y-scroll for a zoom coefficient
-                let dzoom = (-*y / 1000.0f64) as f32;
-                self.zoom(self.cursor_pos, dzoom);
+                let dzoom = -*y as f32 * self.zoom_sensitivity.pixel;
+                self.scroll_zoom(dzoom);
+                true
+            }
+            MouseWheel {
+                delta: MouseScrollDelta::LineDelta(_, y),
+                ..
+            } => {
+                let dzoom = -*y * self.zoom_sensitivity.line;
+                self.scroll_zoom(dzoom);
                 true
             }
+            WindowEvent::Touch(touch) => self.touch(touch),
             //handle the mouse cursor movements if pressd
             _ => false,
         }
     }
 
-    fn zoom(&mut self, position: PhysicalPosition<f64>, dzoom: f32) {
-        //zoom in on a point, dont really get this code
-        //calculate a global top
-        let zoom = 2.0f32.powf(-dzoom);
+    //two fingers pinching zoom toward the point between them
+    //only touch screens pinch, winit 0.27 has no event for trackpad pinches, which scroll instead
+    fn touch(&mut self, touch: &Touch) -> bool {
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touches.insert(touch.id, touch.location);
+                false
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+                false
+            }
+            TouchPhase::Moved => {
+                let pinching = self.touches.len() == 2;
+                let other = self
+                    .touches
+                    .iter()
+                    .find(|(id, _)| **id != touch.id)
+                    .map(|(_, position)| *position);
+                let previous = self.touches.insert(touch.id, touch.location);
 
-        //convert the position to graph space for later
-        let x = self.left + position.x as f32 / self.scale;
-        let y = self.top - position.y as f32 / self.scale;
+                let (other, previous) = match (pinching, other, previous) {
+                    (true, Some(other), Some(previous)) => (other, previous),
+                    _ => return false,
+                };
+                let distance = |a: PhysicalPosition<f64>, b: PhysicalPosition<f64>| {
+                    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+                };
+                let old_distance = distance(previous, other);
+                let new_distance = distance(touch.location, other);
+                if old_distance <= 0f64 || new_distance <= 0f64 {
+                    return false;
+                }
 
-        //calculate the old positio
-        let og_x = position.x as f32;
-        let og_y = position.y as f32;
+                //fingers moving apart zoom in
+                let dzoom =
+                    -(new_distance / old_distance).log2() as f32 * self.zoom_sensitivity.pinch;
+                let midpoint = PhysicalPosition::new(
+                    (touch.location.x + other.x) / 2.0,
+                    (touch.location.y + other.y) / 2.0,
+                );
+                self.zoom(midpoint, dzoom, dzoom);
+                true
+            }
+        }
+    }
 
-        self.scale *= zoom;
-        self.update();
+    //shift scrolls only the x axis and alt only the y axis, unless the aspect is locked
+    fn scroll_zoom(&mut self, dzoom: f32) {
+        let (dzoom_x, dzoom_y) = if self.lock_aspect {
+            (dzoom, dzoom)
+        } else if self.modifiers.shift() {
+            (dzoom, 0f32)
+        } else if self.modifiers.alt() {
+            (0f32, dzoom)
+        } else {
+            (dzoom, dzoom)
+        };
+        self.ease_zoom(self.cursor_pos, dzoom_x, dzoom_y);
+    }
 
-        let new_x = (x - self.left) * self.scale;
-        let new_y = (self.top - y) * self.scale;
+    //queue up a zoom for tick to ease in, a flight in progress is cancelled
+    fn ease_zoom(&mut self, position: PhysicalPosition<f64>, dzoom_x: f32, dzoom_y: f32) {
+        self.flight = None;
+        let (scale_x, scale_y) = if self.zoom_remaining == (0f32, 0f32) {
+            self.scale()
+        } else {
+            self.zoom_target
+        };
+        self.zoom_target = (scale_x * 2f32.powf(-dzoom_x), scale_y * 2f32.powf(-dzoom_y));
+        self.zoom_anchor = position;
+        self.zoom_remaining.0 += dzoom_x;
+        self.zoom_remaining.1 += dzoom_y;
+    }
 
-        //and then translate so that the mouse stays at the same position
-        self.center_x += (new_x - og_x) / self.scale;
-        self.center_y -= (new_y - og_y) / self.scale;
+    fn zoom(&mut self, position: PhysicalPosition<f64>, dzoom_x: f32, dzoom_y: f32) {
+        //each step of dzoom halves or doubles the scale, zoom_to keeps the point under position fixed
+        let zoom_x = 2.0f32.powf(-dzoom_x);
+        let zoom_y = 2.0f32.powf(-dzoom_y);
+        self.zoom_to(position, (self.scale_x * zoom_x, self.scale_y * zoom_y));
+    }
+
+    //change the scale keeping the graph point under a screen position where it is
+    fn zoom_to(&mut self, position: PhysicalPosition<f64>, (scale_x, scale_y): (f32, f32)) {
+        //convert the position to graph space for later
+        let point = self.view().screen_to_graph(position);
+
+        self.scale_x = scale_x;
+        self.scale_y = scale_y;
+        self.update();
+
+        //and then translate so that the point under the mouse stays at the same position
+        let moved_to = self.view().graph_to_screen(point);
+        let (dx, dy) = self
+            .view()
+            .screen_vector_to_graph((moved_to.x - position.x, moved_to.y - position.y));
+        self.center_x += dx;
+        self.center_y += dy;
         self.update();
     }
 
     fn update(&mut self) {
         //update the bounds
-        let right_relative = self.resolution.width as f32 / 2.0 / self.scale;
-        let top_relative = self.resolution.height as f32 / 2.0 / self.scale;
+        let right_relative = self.resolution.width as f32 / 2.0 / self.scale_x;
+        let top_relative = self.resolution.height as f32 / 2.0 / self.scale_y;
 
         self.left = self.center_x - right_relative;
         self.right = self.center_x + right_relative;
@@ -164,25 +627,23 @@ pub struct CameraMatrix {
     pub view_ortho: [[f32; 4]; 4],
 }
 
-//cam controller should be cloned before calling into as
-//generally we want to keep these things alive, this should be a relatively cheap clone
-
-impl Into<CameraMatrix> for CameraController {
-    fn into(self) -> CameraMatrix {
+//built from a view so the gpu only needs updating when the view actually changes
+impl From<&View> for CameraMatrix {
+    fn from(view: &View) -> Self {
         //construct the matrices here
-        let view = cgmath::Matrix4::look_at_lh(
-            (self.center_x, self.center_y, 0f32).into(),
-            (self.center_x, self.center_y, 1f32).into(),
+        let look_at = cgmath::Matrix4::look_at_lh(
+            (view.center_x, view.center_y, 0f32).into(),
+            (view.center_x, view.center_y, 1f32).into(),
             cgmath::Vector3::<f32>::unit_y(),
         );
         //relative top, bottom, left, right (camera space)
-        let left = self.left - self.center_x;
-        let right = self.right - self.center_x;
-        let bottom = self.bottom - self.center_y;
-        let top = self.top - self.center_y;
+        let left = view.left - view.center_x;
+        let right = view.right - view.center_x;
+        let bottom = view.bottom - view.center_y;
+        let top = view.top - view.center_y;
 
         let ortho = cgmath::ortho(left, right, bottom, top, 1f32, -1f32);
-        let combined_matrix = OPENGL_TO_WGPU_MATRIX * ortho * view;
+        let combined_matrix = OPENGL_TO_WGPU_MATRIX * ortho * look_at;
 
         CameraMatrix {
             view_ortho: combined_matrix.into(),
@@ -190,18 +651,158 @@ impl Into<CameraMatrix> for CameraController {
     }
 }
 
-//could be replaced for a method later, to avoid copying
-impl Into<View> for CameraController {
-    fn into(self) -> View {
+impl CameraMatrix {
+    //draws in physical pixels from the top left of the window, for overlays that don't move with the graph
+    pub fn screen(view: &View) -> Self {
+        let (width, height) = view.screen_size();
+        let ortho = cgmath::ortho(0f32, width, height, 0f32, 1f32, -1f32);
+        CameraMatrix {
+            view_ortho: (OPENGL_TO_WGPU_MATRIX * ortho).into(),
+        }
+    }
+}
+
+impl From<&CameraController> for View {
+    fn from(camera: &CameraController) -> Self {
         View {
-            left: self.left,
-            right: self.right,
-            bottom: self.bottom,
-            top: self.top,
-            center_x: self.center_x,
-            center_y: self.center_y,
-            scale: self.scale,
-            aspect: self.aspect,
+            left: camera.left,
+            right: camera.right,
+            bottom: camera.bottom,
+            top: camera.top,
+            center_x: camera.center_x,
+            center_y: camera.center_y,
+            scale: (camera.scale_x, camera.scale_y),
+            aspect: camera.aspect,
+            scale_factor: camera.scale_factor,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1f32 / 60f32;
+
+    fn camera() -> CameraController {
+        CameraController::new(1f32, -2f32, PhysicalSize::new(800, 600))
+    }
+
+    fn assert_close((x0, y0): (f32, f32), (x1, y1): (f32, f32)) {
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-4 * b.abs().max(1f32);
+        assert!(close(x0, x1) && close(y0, y1), "{:?} != {:?}", (x0, y0), (x1, y1));
+    }
+
+    //tick at a steady rate until nothing moves, returns how many ticks that took
+    fn settle(camera: &mut CameraController, dt: f32) -> usize {
+        let mut ticks = 0;
+        while camera.is_animating() {
+            assert!(ticks < 600, "the camera never came to rest");
+            camera.tick(dt);
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn kinetic_pan_decays_to_rest() {
+        let mut camera = camera();
+        //a drag that moved 40 pixels in the last tick before it was let go
+        camera.pressed = true;
+        camera.drag_pixels = (40f32, -10f32);
+        camera.tick(FRAME);
+        camera.pressed = false;
+        assert_close(camera.velocity, (2400f32, -600f32));
+
+        let released_at = camera.center();
+        let mut speed = f32::INFINITY;
+        while camera.velocity != (0f32, 0f32) {
+            assert!(camera.tick(FRAME));
+            let new_speed = camera.velocity.0.hypot(camera.velocity.1);
+            assert!(new_speed < speed);
+            speed = new_speed;
+        }
+        assert_ne!(camera.center(), released_at);
+        assert!(!camera.is_animating());
+        assert!(!camera.tick(FRAME));
+    }
+
+    #[test]
+    fn holding_still_is_not_animating() {
+        let mut camera = camera();
+        camera.pressed = true;
+        assert!(!camera.is_animating());
+
+        //the cursor moves for a tick and then stops with the button still held
+        camera.drag_pixels = (40f32, -10f32);
+        assert!(camera.is_animating());
+        camera.tick(FRAME);
+        camera.tick(FRAME);
+        assert!(!camera.is_animating());
+
+        //so letting go doesn't fling the view
+        camera.pressed = false;
+        assert!(!camera.tick(FRAME));
+        assert!(!camera.is_animating());
+    }
+
+    #[test]
+    fn fling_does_not_depend_on_the_frame_rate() {
+        let mut once = camera();
+        once.velocity = (600f32, -300f32);
+        let mut twice = once.clone();
+
+        once.tick(1f32 / 30f32);
+        twice.tick(1f32 / 60f32);
+        twice.tick(1f32 / 60f32);
+        assert_close(once.center(), twice.center());
+        assert_close(once.velocity, twice.velocity);
+    }
+
+    #[test]
+    fn eased_zoom_does_not_depend_on_the_frame_rate() {
+        let mut once = camera();
+        once.zoom_center(-1f32);
+        let mut twice = once.clone();
+
+        once.tick(1f32 / 30f32);
+        twice.tick(1f32 / 60f32);
+        twice.tick(1f32 / 60f32);
+        assert_close(once.scale(), twice.scale());
+        assert_close(once.center(), twice.center());
+    }
+
+    #[test]
+    fn eased_zoom_lands_on_its_target() {
+        let mut camera = camera();
+        let cursor = PhysicalPosition::new(200.0, 150.0);
+        camera.cursor_pos = cursor;
+        let under_cursor = camera.view().screen_to_graph(cursor);
+
+        //two notches of zooming in, the second before the first has finished
+        camera.scroll_zoom(-1f32);
+        camera.tick(FRAME);
+        camera.scroll_zoom(-1f32);
+        settle(&mut camera, FRAME);
+
+        assert_eq!(camera.scale(), (DEFAULT_SCALE * 4f32, DEFAULT_SCALE * 4f32));
+        assert_close(camera.view().screen_to_graph(cursor), under_cursor);
+        assert!(!camera.tick(FRAME));
+    }
+
+    #[test]
+    fn fly_to_lands_on_its_target() {
+        let mut target = camera();
+        target.look_at(10f32, 5f32, (7f32, 3f32));
+
+        let mut camera = camera();
+        camera.fly_to(&target.view());
+        let ticks = settle(&mut camera, FRAME);
+
+        assert_eq!(camera.center(), (10f32, 5f32));
+        assert_eq!(camera.scale(), (7f32, 3f32));
+        let frames = FLY_DURATION / FRAME;
+        assert!(frames.floor() as usize <= ticks && ticks <= frames.ceil() as usize + 1);
+        assert!(!camera.tick(FRAME));
+    }
+}
@@ -2,6 +2,7 @@ mod view;
 mod camera;
 pub mod primitives;
 
-pub use view::View;
+pub use view::{Rect, View};
 pub use camera::CameraController;
+pub use camera::ZoomSensitivity;
 pub use camera::CameraMatrix;
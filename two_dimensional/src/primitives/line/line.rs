@@ -1,6 +1,6 @@
 use super::LineVertex;
 
-use crate::View;
+use crate::{Rect, View};
 
 //we're basically going to need a way to decide what lines to pass to our renderer
 //because we're working in infinite space
@@ -11,7 +11,7 @@ use crate::View;
 //this means that we're regenerating every time the view changes
 //objects need to be smart and know how to update themselves and when to update themselves based on the view
 pub struct Line {
-    pub width: f32, //width in graph space
+    pub width: f32, //width in pixels
     pub start: (f32, f32),
     pub end: (f32, f32),
     pub color: [f32; 3],
@@ -19,45 +19,48 @@ pub struct Line {
 
 impl Line {
     pub fn get_vertices(&self, view: &View) -> Vec<LineVertex> {
-        //some fancy trigonometry for generating 4 points from start and end of line
-        //could probably do some math optimization here
+        //generate 4 points from start and end of line by offsetting both ends along the normal
+        //the normal is found in pixels and converted back, so the width holds when x and y are scaled differently
         let half_width = self.width / 2f32;
-        let se_length = ((self.start.0 - self.end.0).powf(2f32) + (self.start.1 - self.end.1).powf(2f32)).sqrt();
-        let sf = half_width / se_length;
-        let sign_correction = -1f32 * ((self.end.1 - self.start.1) / (self.end.0 - self.start.0)).signum(); //ensures the sign is opposite the sign of the line
-        let dx = sign_correction * sf * (self.end.1 - self.start.1).abs() * view.aspect.0;
-        let dy = sf * (self.end.0 - self.start.0).abs() * view.aspect.1;
+        let pixel_dx = (self.end.0 - self.start.0) / view.aspect.0;
+        let pixel_dy = (self.end.1 - self.start.1) / view.aspect.1;
+        let pixel_length = (pixel_dx * pixel_dx + pixel_dy * pixel_dy).sqrt();
+        let sf = half_width / pixel_length;
+        let dx = -pixel_dy * sf * view.aspect.0;
+        let dy = pixel_dx * sf * view.aspect.1;
+        let [r, g, b] = self.color;
+        let color = [r, g, b, 1f32];
         let vertices = vec![
             LineVertex {
                 position: [self.start.0 - dx, self.start.1 - dy],
-                color: self.color,
+                color,
             },
             LineVertex {
                 position: [self.start.0 + dx, self.start.1 + dy],
-                color: self.color,
+                color,
             },
             LineVertex {
                 position: [self.end.0 - dx, self.end.1 - dy],
-                color: self.color,
+                color,
             },
             LineVertex {
                 position: [self.end.0 + dx, self.end.1 + dy],
-                color: self.color,
+                color,
             },
         ];
         vertices
     }
 
-    pub fn get_indices(&self) -> Vec<u16> {
+    pub fn get_indices(&self) -> Vec<u32> {
         vec![0, 1, 2, 3, 2, 1]
     }
 }
 
 //TODO: maybe make this private members with getters
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct LineList {
     vertices: Vec<LineVertex>,
-    indices: Vec<u16>
+    indices: Vec<u32>
 }
 
 impl LineList {
@@ -77,35 +80,94 @@ impl LineList {
     }
 
     pub fn add_line(&mut self, line: &Line, view: &View) {
-        let vertices_size = self.vertices.len() as u16;
+        let vertices_size = self.vertices.len() as u32;
         let mut line_vertices = line.get_vertices(view);
         self.vertices.append(&mut line_vertices);
 
         let line_indices = line.get_indices();
-        let mut adjusted_indices: Vec<u16> = line_indices.iter().map(|i| i + vertices_size).collect();
+        let mut adjusted_indices: Vec<u32> = line_indices.iter().map(|i| i + vertices_size).collect();
         self.indices.append(&mut adjusted_indices);
     }
 
     //be able to append line lists
     pub fn _append(&mut self, other: &mut LineList) {
-        let vertices_size = self.vertices.len() as u16;
+        let vertices_size = self.vertices.len() as u32;
         self.vertices.append(&mut other.vertices);
 
-        let mut adjusted_indices: Vec<u16> = other.indices.iter().map(|i| i + vertices_size).collect();
+        let mut adjusted_indices: Vec<u32> = other.indices.iter().map(|i| i + vertices_size).collect();
         self.indices.append(&mut adjusted_indices);
     }
 
+    //copy another line list onto the end of this one, leaving the other intact
+    pub fn extend(&mut self, other: &LineList) {
+        let vertices_size = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| i + vertices_size));
+    }
+
+    //a filled convex polygon in graph space, drawn as a fan of triangles from the first point
+    //the color has an alpha, so the polygon can be see through
+    pub fn add_polygon(&mut self, points: &[(f32, f32)], color: [f32; 4]) {
+        if points.len() < 3 {
+            return;
+        }
+        let vertices_size = self.vertices.len() as u32;
+        self.vertices.extend(points.iter().map(|&(x, y)| LineVertex {
+            position: [x, y],
+            color,
+        }));
+        for i in 1..points.len() as u32 - 1 {
+            self.indices.extend([vertices_size, vertices_size + i, vertices_size + i + 1]);
+        }
+    }
+
     pub fn append_vec(&mut self, lines: &[Line], view: &View) {
         for line in lines {
             self.add_line(line, view)
         }
     }
 
+    //the smallest rectangle in graph space holding every vertex
+    pub fn bounds(&self) -> Option<Rect> {
+        let mut vertices = self.vertices.iter();
+        let [x, y] = vertices.next()?.position;
+        let first = Rect::from_corners((x, y), (x, y));
+        Some(vertices.fold(first, |rect, vertex| {
+            let [x, y] = vertex.position;
+            rect.union(&Rect::from_corners((x, y), (x, y)))
+        }))
+    }
+
     pub fn vertices(&self) -> &[LineVertex] {
         self.vertices.as_ref()
     }
 
-    pub fn indices(&self) -> &[u16] {
+    pub fn indices(&self) -> &[u32] {
         self.indices.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [f32; 4] = [1f32; 4];
+
+    //every plot is merged into one list, so together they go well past what 16 bit indices can reach
+    #[test]
+    fn extend_past_u16_vertices() {
+        let mut quad = LineList::new();
+        quad.add_polygon(&[(0f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0f32, 1f32)], WHITE);
+
+        let mut merged = LineList::new();
+        for _ in 0..20_000 {
+            merged.extend(&quad);
+        }
+
+        assert_eq!(merged.vertices().len(), 80_000);
+        assert_eq!(merged.indices().len(), 20_000 * 6);
+        assert_eq!(merged.indices().iter().max(), Some(&79_999));
+        let last = &merged.indices()[merged.indices().len() - 6..];
+        assert_eq!(last, [79_996, 79_997, 79_998, 79_996, 79_998, 79_999]);
+    }
+}
@@ -1,4 +1,5 @@
 mod vertex;
+#[allow(clippy::module_inception)]
 mod line;
 mod rect_pipeline;
 
@@ -8,5 +9,5 @@ pub use line::Line;
 pub use line::LineList;
 pub use rect_pipeline::RectPipeline;
 pub use rect_pipeline::render as render_lines;
-pub use rect_pipeline::RenderPassData as LinePassData;
 pub use rect_pipeline::CameraUniform;
+pub use rect_pipeline::LineBuffers;
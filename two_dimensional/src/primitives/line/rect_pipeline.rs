@@ -1,10 +1,10 @@
 use bevy_ecs::prelude::*;
 
-use rendering::RenderContext;
+use rendering::{Frame, RenderContext, RenderPhase};
 use wgpu::util::DeviceExt;
 
 use super::{line::LineList, LineVertex};
-use crate::View;
+use crate::CameraMatrix;
 
 //TODO this object should take a view of an ECS
 //where all it can see is Rect objects, but for now
@@ -12,92 +12,167 @@ pub struct RectPipeline {
     pipeline: wgpu::RenderPipeline,
 }
 
+//the camera matrix on the gpu, shared by every pipeline that draws in graph space
 pub struct CameraUniform {
     bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
     buffer: wgpu::Buffer,
 }
 
-pub struct RenderPassData {
-    pub view: View,
-    pub lines: LineList,
-}
-
-fn generate_buffers(
-    render_context: &RenderContext,
-    lines: LineList,
-) -> (wgpu::Buffer, wgpu::Buffer, u32) {
-    let vertices: &[LineVertex] = lines.vertices();
-    let indices: &[u16] = lines.indices();
-
-    //in with the new
-    let vertex_buffer =
-        render_context
+impl CameraUniform {
+    pub fn new(render_context: &RenderContext, camera_matrix: CameraMatrix) -> Self {
+        let buffer = render_context
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: bytemuck::cast_slice(vertices),
-                usage: wgpu::BufferUsages::VERTEX,
+                label: Some("Camera Buffer"),
+                contents: bytemuck::cast_slice(&[camera_matrix]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
-    let index_buffer =
-        render_context
+        let bind_group_layout =
+            render_context
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("camera_bind_group_layout"),
+                });
+
+        let bind_group = render_context
             .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(indices),
-                usage: wgpu::BufferUsages::INDEX,
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+                label: Some("camera_bind_group"),
             });
-    let num_indices = indices.len() as u32;
 
-    (vertex_buffer, index_buffer, num_indices)
+        Self {
+            bind_group_layout,
+            bind_group,
+            buffer,
+        }
+    }
+
+    pub fn update(&self, render_context: &RenderContext, camera_matrix: CameraMatrix) {
+        render_context
+            .queue
+            .write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[camera_matrix]));
+    }
+
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+}
+
+//the gpu copy of the line list, only rebuilt when the line list changes
+pub struct LineBuffers {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+}
+
+impl LineBuffers {
+    //None for an empty line list, there is nothing to draw
+    pub fn new(render_context: &RenderContext, lines: &LineList) -> Option<Self> {
+        let vertices: &[LineVertex] = lines.vertices();
+        let indices: &[u32] = lines.indices();
+        if indices.is_empty() {
+            return None;
+        }
+
+        //in with the new
+        let vertex_buffer =
+            render_context
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Buffer"),
+                    contents: bytemuck::cast_slice(vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        let index_buffer =
+            render_context
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Index Buffer"),
+                    contents: bytemuck::cast_slice(indices),
+                    usage: wgpu::BufferUsages::INDEX,
+                });
+        let num_indices = indices.len() as u32;
+
+        Some(Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+        })
+    }
 }
 
-//define a bevy render system
+//define a bevy render system, this draws every line in the line list into the current frame
 pub fn render(
-    In(render_pass_data): In<RenderPassData>,
     rect_pipeline: Res<RectPipeline>,
     render_context: Res<RenderContext>,
-    surface_view: Res<wgpu::TextureView>,
     camera_uniform: Res<CameraUniform>,
-    mut command_buffers: ResMut<Vec<wgpu::CommandBuffer>>,
+    lines: Res<LineList>,
+    frame: Option<ResMut<Frame>>,
+    mut buffers: Local<Option<LineBuffers>>,
 ) {
     //generate our vertex and index buffers here from our vertex data and index data
-    let (vertex_buffer, index_buffer, num_indices) =
-        generate_buffers(&render_context, render_pass_data.lines);
-
-    let mut encoder =
-        render_context
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Line Command Encoder"),
-            });
-
-    {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &surface_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
-
-        render_pass.set_pipeline(&rect_pipeline.pipeline);
-        render_pass.set_bind_group(0, &camera_uniform.bind_group, &[]);
-        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..num_indices, 0, 0..1);
+    if lines.is_changed() || buffers.is_none() {
+        *buffers = LineBuffers::new(&render_context, &lines);
     }
 
-    command_buffers.as_mut().push(encoder.finish());
+    //no frame means the surface couldn't give us a texture this time around
+    if let (Some(mut frame), Some(buffers)) = (frame, buffers.as_ref()) {
+        rect_pipeline.draw(&render_context, &camera_uniform, &mut frame, RenderPhase::Line, buffers);
+    }
 }
 
 impl RectPipeline {
+    //record a pass drawing the buffers in graph space, for any system that keeps its own lines
+    pub fn draw(
+        &self,
+        render_context: &RenderContext,
+        camera_uniform: &CameraUniform,
+        frame: &mut Frame,
+        phase: RenderPhase,
+        buffers: &LineBuffers,
+    ) {
+        let mut encoder =
+            render_context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Line Command Encoder"),
+                });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(frame.color_attachment())],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &camera_uniform.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(buffers.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..buffers.num_indices, 0, 0..1);
+        }
+
+        frame.add_pass(phase, encoder.finish());
+    }
+
     pub fn new(render_context: &RenderContext, camera_layout: &wgpu::BindGroupLayout) -> Self {
         let bind_group_layouts = &[camera_layout];
 
@@ -135,7 +210,7 @@ impl RectPipeline {
                         targets: &[Some(wgpu::ColorTargetState {
                             // 4.
                             format: render_context.config.format,
-                            blend: Some(wgpu::BlendState::REPLACE),
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                             write_mask: wgpu::ColorWrites::ALL,
                         })],
                     }),
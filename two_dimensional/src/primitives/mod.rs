@@ -1,2 +1,2 @@
 pub mod line;
-mod text;
\ No newline at end of file
+pub mod text;
\ No newline at end of file
@@ -1,3 +1,5 @@
 mod text_pipeline;
+mod font;
 
-pub use text_pipeline::TextPipeline;
\ No newline at end of file
+pub use text_pipeline::TextPipeline;
+pub use font::{add_text, text_size, GLYPH_HEIGHT, GLYPH_WIDTH};
@@ -1,5 +1,8 @@
+use winit::dpi::{LogicalPosition, PhysicalPosition};
+
 //this object is created by a camera and used by renderable objects
 //views will change depending on the camera, for example this is a 2d ortho view
+#[derive(Clone, PartialEq)]
 pub struct View {
     pub left: f32,
     pub right: f32,
@@ -7,8 +10,156 @@ pub struct View {
     pub top: f32,
     pub center_x: f32,
     pub center_y: f32,
-    pub scale: f32,
-    pub aspect: (f32, f32)
+    pub scale: (f32, f32), //pixels per graph unit along x and y
+    pub aspect: (f32, f32),
+    pub scale_factor: f64, //physical pixels per logical pixel
+}
+
+//an axis aligned rectangle in graph space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl Rect {
+    //the rectangle with two opposite corners at these points, in any order
+    pub fn from_corners((x0, y0): (f32, f32), (x1, y1): (f32, f32)) -> Self {
+        Self {
+            left: x0.min(x1),
+            right: x0.max(x1),
+            bottom: y0.min(y1),
+            top: y0.max(y1),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        ((self.left + self.right) / 2f32, (self.bottom + self.top) / 2f32)
+    }
+
+    pub fn contains(&self, (x, y): (f32, f32)) -> bool {
+        x >= self.left && x <= self.right && y >= self.bottom && y <= self.top
+    }
+
+    //the smallest rectangle holding both
+    pub fn union(&self, other: &Rect) -> Self {
+        Self {
+            left: self.left.min(other.left),
+            right: self.right.max(other.right),
+            bottom: self.bottom.min(other.bottom),
+            top: self.top.max(other.top),
+        }
+    }
+
+    //grown on every side by a fraction of its size
+    pub fn padded(&self, fraction: f32) -> Self {
+        let pad_x = self.width() * fraction;
+        let pad_y = self.height() * fraction;
+        Self {
+            left: self.left - pad_x,
+            right: self.right + pad_x,
+            bottom: self.bottom - pad_y,
+            top: self.top + pad_y,
+        }
+    }
+}
+
+//conversions between the screen and graph space, screen space starts at the top left of the window
+//with y going down, graph space has y going up
+//physical pixels are the ones winit reports cursor positions in, logical pixels are scaled by the
+//scale factor so they stay the same size on high dpi screens
+impl View {
+    pub fn screen_to_graph(&self, position: PhysicalPosition<f64>) -> (f32, f32) {
+        (
+            self.left + position.x as f32 / self.scale.0,
+            self.top - position.y as f32 / self.scale.1,
+        )
+    }
+
+    pub fn graph_to_screen(&self, (x, y): (f32, f32)) -> PhysicalPosition<f64> {
+        PhysicalPosition::new(
+            ((x - self.left) * self.scale.0) as f64,
+            ((self.top - y) * self.scale.1) as f64,
+        )
+    }
+
+    pub fn logical_to_graph(&self, position: LogicalPosition<f64>) -> (f32, f32) {
+        self.screen_to_graph(position.to_physical(self.scale_factor))
+    }
+
+    pub fn graph_to_logical(&self, point: (f32, f32)) -> LogicalPosition<f64> {
+        self.graph_to_screen(point).to_logical(self.scale_factor)
+    }
+
+    //a distance rather than a position, so only the scale and the flipped y apply
+    pub fn screen_vector_to_graph(&self, (dx, dy): (f64, f64)) -> (f32, f32) {
+        (dx as f32 / self.scale.0, -dy as f32 / self.scale.1)
+    }
+
+    pub fn graph_vector_to_screen(&self, (dx, dy): (f32, f32)) -> (f64, f64) {
+        ((dx * self.scale.0) as f64, (-dy * self.scale.1) as f64)
+    }
+
+    pub fn logical_vector_to_graph(&self, (dx, dy): (f64, f64)) -> (f32, f32) {
+        self.screen_vector_to_graph((dx * self.scale_factor, dy * self.scale_factor))
+    }
+
+    pub fn graph_vector_to_logical(&self, vector: (f32, f32)) -> (f64, f64) {
+        let (dx, dy) = self.graph_vector_to_screen(vector);
+        (dx / self.scale_factor, dy / self.scale_factor)
+    }
+
+    //the graph space rectangle between two opposite screen corners
+    pub fn screen_rect_to_graph(&self, a: PhysicalPosition<f64>, b: PhysicalPosition<f64>) -> Rect {
+        Rect::from_corners(self.screen_to_graph(a), self.screen_to_graph(b))
+    }
+
+    //the top left and bottom right screen corners of a graph space rectangle
+    pub fn graph_rect_to_screen(&self, rect: &Rect) -> (PhysicalPosition<f64>, PhysicalPosition<f64>) {
+        (
+            self.graph_to_screen((rect.left, rect.top)),
+            self.graph_to_screen((rect.right, rect.bottom)),
+        )
+    }
+
+    pub fn logical_rect_to_graph(&self, a: LogicalPosition<f64>, b: LogicalPosition<f64>) -> Rect {
+        Rect::from_corners(self.logical_to_graph(a), self.logical_to_graph(b))
+    }
+
+    pub fn graph_rect_to_logical(&self, rect: &Rect) -> (LogicalPosition<f64>, LogicalPosition<f64>) {
+        (
+            self.graph_to_logical((rect.left, rect.top)),
+            self.graph_to_logical((rect.right, rect.bottom)),
+        )
+    }
+
+    //the size of the window in physical pixels
+    pub fn screen_size(&self) -> (f32, f32) {
+        (
+            (self.right - self.left) * self.scale.0,
+            (self.top - self.bottom) * self.scale.1,
+        )
+    }
+
+    //everything the view shows
+    pub fn rect(&self) -> Rect {
+        Rect {
+            left: self.left,
+            right: self.right,
+            bottom: self.bottom,
+            top: self.top,
+        }
+    }
 }
 
 //so one thing to think about is that literally everything goes through the renderer in a way
@@ -22,4 +173,107 @@ pub struct View {
 //one possibility is to automatically register certain components to objects that implement other components
 //so for example a clickable component interface, that is automatically registered to a component if it has a renderable
 
-//events can be very abstract
\ No newline at end of file
+//events can be very abstract
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //8 by 6 graph units on a 800 by 300 physical pixel window, at 1.5 physical pixels per logical one
+    fn view() -> View {
+        View {
+            left: -2f32,
+            right: 6f32,
+            bottom: -1f32,
+            top: 5f32,
+            center_x: 2f32,
+            center_y: 2f32,
+            scale: (100f32, 50f32),
+            aspect: (1f32 / 100f32, 1f32 / 50f32),
+            scale_factor: 1.5,
+        }
+    }
+
+    fn assert_close((x0, y0): (f32, f32), (x1, y1): (f32, f32)) {
+        assert!((x0 - x1).abs() < 1e-4 && (y0 - y1).abs() < 1e-4, "{:?} != {:?}", (x0, y0), (x1, y1));
+    }
+
+    fn assert_close_f64((x0, y0): (f64, f64), (x1, y1): (f64, f64)) {
+        assert!((x0 - x1).abs() < 1e-3 && (y0 - y1).abs() < 1e-3, "{:?} != {:?}", (x0, y0), (x1, y1));
+    }
+
+    #[test]
+    fn screen_to_graph_flips_y() {
+        let view = view();
+        //x = left + 300 / 100, y = top - 100 / 50
+        assert_close(view.screen_to_graph(PhysicalPosition::new(300.0, 100.0)), (1f32, 3f32));
+        assert_close(view.screen_to_graph(PhysicalPosition::new(0.0, 0.0)), (-2f32, 5f32));
+        assert_close(view.screen_to_graph(PhysicalPosition::new(800.0, 300.0)), (6f32, -1f32));
+
+        let screen = view.graph_to_screen((0f32, 0f32));
+        assert_close_f64((screen.x, screen.y), (200.0, 250.0));
+    }
+
+    #[test]
+    fn logical_positions_are_scaled() {
+        let view = view();
+        //150 logical pixels are 225 physical ones
+        assert_close(view.logical_to_graph(LogicalPosition::new(150.0, 60.0)), (0.25, 3.2));
+        let logical = view.graph_to_logical((0.25, 3.2));
+        assert_close_f64((logical.x, logical.y), (150.0, 60.0));
+    }
+
+    #[test]
+    fn points_round_trip() {
+        let view = view();
+        for point in [(0f32, 0f32), (1.25, -0.5), (-2f32, 5f32), (5.5, 4.75)] {
+            assert_close(view.screen_to_graph(view.graph_to_screen(point)), point);
+            assert_close(view.logical_to_graph(view.graph_to_logical(point)), point);
+        }
+        let screen = PhysicalPosition::new(123.0, 45.0);
+        let back = view.graph_to_screen(view.screen_to_graph(screen));
+        assert_close_f64((back.x, back.y), (123.0, 45.0));
+        let logical = LogicalPosition::new(67.0, 89.0);
+        let back = view.graph_to_logical(view.logical_to_graph(logical));
+        assert_close_f64((back.x, back.y), (67.0, 89.0));
+    }
+
+    #[test]
+    fn vectors_ignore_the_origin() {
+        let view = view();
+        //down the screen is down the graph
+        assert_close(view.screen_vector_to_graph((100.0, 50.0)), (1f32, -1f32));
+        assert_close(view.logical_vector_to_graph((100.0, 50.0)), (1.5, -1.5));
+        assert_close_f64(view.graph_vector_to_screen((1f32, -1f32)), (100.0, 50.0));
+        assert_close_f64(view.graph_vector_to_logical((1.5, -1.5)), (100.0, 50.0));
+
+        for vector in [(0.5f32, 2f32), (-3f32, -0.25)] {
+            assert_close(view.screen_vector_to_graph(view.graph_vector_to_screen(vector)), vector);
+            assert_close(view.logical_vector_to_graph(view.graph_vector_to_logical(vector)), vector);
+        }
+    }
+
+    #[test]
+    fn rects_round_trip() {
+        let view = view();
+        let rect = Rect {
+            left: -1f32,
+            right: 3f32,
+            bottom: 0.5,
+            top: 4f32,
+        };
+
+        let (top_left, bottom_right) = view.graph_rect_to_screen(&rect);
+        assert_close_f64((top_left.x, top_left.y), (100.0, 50.0));
+        assert_close_f64((bottom_right.x, bottom_right.y), (500.0, 225.0));
+        let back = view.screen_rect_to_graph(top_left, bottom_right);
+        assert_close((back.left, back.bottom), (rect.left, rect.bottom));
+        assert_close((back.right, back.top), (rect.right, rect.top));
+
+        //corners given in the other order still make the same rectangle
+        let (top_left, bottom_right) = view.graph_rect_to_logical(&rect);
+        let back = view.logical_rect_to_graph(bottom_right, top_left);
+        assert_close((back.left, back.bottom), (rect.left, rect.bottom));
+        assert_close((back.right, back.top), (rect.right, rect.top));
+    }
+}